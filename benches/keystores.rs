@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use trustchain::v3::{BTreeKeysStore, HashKeysStore, PublicKey, RootKeysStore, PUBLICKEYBYTES};
+
+fn key (n: u32) -> PublicKey {
+    PublicKey::from_slice(&n.to_be_bytes().repeat(PUBLICKEYBYTES / 4)).unwrap()
+}
+
+fn lookup <'ks> (store: &'ks dyn RootKeysStore<'ks>, candidates: &[PublicKey]) -> usize {
+    candidates.iter().filter(|k| store.contains_root_key(black_box(k.as_ref()))).count()
+}
+
+fn lookup_roots (c: &mut Criterion) {
+    let mut group = c.benchmark_group("contains_root_key");
+
+    for roots in [3u32, 1_000, 10_000].iter() {
+        // half the candidates are held, half are not, spread across the key space.
+        let candidates = (0..64).map(|i| key(i * roots / 32)).collect::<Vec<_>>();
+        let vec_store  = (0..*roots).map(key).collect::<Vec<_>>();
+        let hash_store = HashKeysStore::from_keys(vec_store.iter().copied()).unwrap();
+        let tree_store = BTreeKeysStore::from_keys(vec_store.iter().copied()).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("vec", roots), &candidates, |b, c| b.iter(|| lookup(&vec_store, c)));
+        group.bench_with_input(BenchmarkId::new("hash_set", roots), &candidates, |b, c| b.iter(|| lookup(&hash_store, c)));
+        group.bench_with_input(BenchmarkId::new("btree_set", roots), &candidates, |b, c| b.iter(|| lookup(&tree_store, c)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lookup_roots);
+criterion_main!(benches);
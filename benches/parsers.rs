@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use trustchain::v3::{self, ChainBuilder, FixedClock, PublicKey};
+
+fn encoded_chain (links: usize, root_keypair: &Ed25519KeyPair, root_key_store: &Vec<PublicKey>) -> Vec<u8> {
+    let mut builder = ChainBuilder::new(root_keypair);
+    for _ in 2..links {
+        builder = builder.intermediate(None);
+    }
+    let (chain, _) = builder.build(None, root_key_store, &FixedClock::new(0)).unwrap();
+    chain.as_bytes()
+}
+
+fn parse_chains (c: &mut Criterion) {
+    let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x01;32]).unwrap();
+    let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+    let clock          = FixedClock::new(0);
+    let mut group      = c.benchmark_group("trust_chain");
+
+    for links in [2, 5].iter() {
+        let cb = encoded_chain(*links, &root_keypair, &root_key_store);
+
+        group.bench_with_input(BenchmarkId::new("owned", links), &cb, |b, cb| {
+            b.iter(|| v3::trust_chain(&mut black_box(cb.as_slice()), &root_key_store, &clock).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", links), &cb, |b, cb| {
+            b.iter(|| v3::trust_chain_ref(black_box(cb.as_slice()), &root_key_store, &clock).unwrap().len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_chains);
+criterion_main!(benches);
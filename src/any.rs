@@ -0,0 +1,266 @@
+#[cfg(feature = "v2")]
+use crate::v2;
+use crate::v3;
+#[cfg(feature = "v4")]
+use crate::v4;
+
+pub const PUBLICKEYBYTES : usize = 32;
+
+// ------------------------[   T,   C]
+pub const TC_TAG: [u8;2] = [0x54,0x43];
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum AnyTrustError {
+    InvalidTrustChain,
+    UnsupportedVersion(u16),
+    #[cfg(feature = "v2")]
+    NotRepresentable,
+    #[cfg(feature = "v2")]
+    V2(v2::TrustError),
+    V3(v3::TrustError),
+    // v4 shares v3's error type, so v4 failures are wrapped explicitly rather than through From.
+    #[cfg(feature = "v4")]
+    V4(v4::TrustError),
+}
+
+#[cfg(feature = "v2")]
+impl From<v2::TrustError> for AnyTrustError {
+    fn from (err: v2::TrustError) -> Self { AnyTrustError::V2(err) }
+}
+
+impl From<v3::TrustError> for AnyTrustError {
+    fn from (err: v3::TrustError) -> Self { AnyTrustError::V3(err) }
+}
+
+//--- A verified trust chain of whichever version the encoding declared
+// v3 chains are held inline and v4 chains on the heap, boxing v3 would only move that size around.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone,Debug,PartialEq)]
+pub enum AnyTrustChain {
+    #[cfg(feature = "v2")]
+    V2(v2::TrustChain),
+    V3(v3::TrustChain),
+    #[cfg(feature = "v4")]
+    V4(v4::TrustChain),
+}
+
+impl AnyTrustChain {
+    pub fn version (&self) -> u16 {
+        match self {
+            #[cfg(feature = "v2")]
+            AnyTrustChain::V2(_) => 2,
+            AnyTrustChain::V3(_) => 3,
+            #[cfg(feature = "v4")]
+            AnyTrustChain::V4(_) => 4,
+        }
+    }
+
+    pub fn root_key (&self) -> [u8;PUBLICKEYBYTES] {
+        match self {
+            #[cfg(feature = "v2")]
+            AnyTrustChain::V2(chain) => chain.root_key().0,
+            AnyTrustChain::V3(chain) => key_bytes(chain.first().key().as_ref()),
+            #[cfg(feature = "v4")]
+            AnyTrustChain::V4(chain) => key_bytes(chain.first().key().as_ref()),
+        }
+    }
+
+    pub fn end_key (&self) -> [u8;PUBLICKEYBYTES] {
+        match self {
+            #[cfg(feature = "v2")]
+            AnyTrustChain::V2(chain) => chain.end_key().0,
+            AnyTrustChain::V3(chain) => key_bytes(chain.last().key().as_ref()),
+            #[cfg(feature = "v4")]
+            AnyTrustChain::V4(chain) => key_bytes(chain.last().key().as_ref()),
+        }
+    }
+
+    pub fn verify_data (
+        &self,
+        untrusted_signature: &[u8],
+        untrusted_data: &[u8],
+        clock: &dyn v3::Clock ) -> Result<(),AnyTrustError> {
+
+        match self {
+            #[cfg(feature = "v2")]
+            AnyTrustChain::V2(chain) => {
+                let sig = sodiumoxide::crypto::sign::ed25519::Signature::from_bytes(untrusted_signature)
+                    .map_err(|_| v2::TrustError::InvalidSignature)?;
+                Ok(chain.verify_data(&sig, untrusted_data)?)
+            },
+            AnyTrustChain::V3(chain) => {
+                let sig = v3::Signature::from_slice(untrusted_signature)
+                    .ok_or(v3::TrustError::InvalidSignature)?;
+                Ok(chain.verify_data(&sig, untrusted_data, clock)?)
+            },
+            #[cfg(feature = "v4")]
+            AnyTrustChain::V4(chain) => {
+                let sig = v4::Signature::from_slice(untrusted_signature)
+                    .ok_or(AnyTrustError::V4(v4::TrustError::InvalidSignature))?;
+                chain.verify_data(&sig, untrusted_data, clock).map_err(AnyTrustError::V4)
+            },
+        }
+    }
+
+    pub fn as_bytes (&self) -> Vec<u8> {
+        match self {
+            #[cfg(feature = "v2")]
+            AnyTrustChain::V2(chain) => chain.as_bytes(),
+            AnyTrustChain::V3(chain) => chain.as_bytes(),
+            #[cfg(feature = "v4")]
+            AnyTrustChain::V4(chain) => chain.as_bytes(),
+        }
+    }
+}
+
+fn key_bytes (key: &[u8]) -> [u8;PUBLICKEYBYTES] {
+    let mut out = [0u8;PUBLICKEYBYTES];
+    out.copy_from_slice(key);
+    out
+}
+
+//--- Reads the "TC" tag and version word, then hands the whole input to that version's decoder
+// Whatever the version, the chain has to start at an anchor of the store that admits it at the clock's time.
+pub fn parse_any <'ks> (
+    input: &[u8],
+    root_keys_store: &'ks (dyn v3::RootKeysStore<'ks> + 'ks),
+    clock: &dyn v3::Clock ) -> Result<AnyTrustChain,AnyTrustError> {
+
+    if input.len() < 4 || input[0..2] != TC_TAG {
+        return Err(AnyTrustError::InvalidTrustChain)
+    }
+
+    match u16::from_be_bytes([input[2], input[3]]) {
+        #[cfg(feature = "v2")]
+        2 => {
+            // the v2 decoder only asks whether its root is trusted, so the anchor's terms are checked here,
+            // and breaking them is reported as the v3 error the anchor raises.
+            let root   = input.get(4..4 + PUBLICKEYBYTES).ok_or(AnyTrustError::InvalidTrustChain)?;
+            let anchor = root_keys_store.trust_anchor(root).ok_or(v2::TrustError::NoRootKeyTrust)?;
+            anchor.admits_at(clock.now())?;
+            let root_key = sodiumoxide::crypto::sign::ed25519::PublicKey::from_slice(root)
+                .ok_or(AnyTrustError::InvalidTrustChain)?;
+            let (_, chain) = v2::trust_chain(input, Box::new(vec!(root_key)))
+                .map_err(|_| AnyTrustError::InvalidTrustChain)?;
+            anchor.allows_depth(input[4 + PUBLICKEYBYTES] as usize)?;
+            Ok(AnyTrustChain::V2(chain))
+        },
+        // 0x0103 is v3 with a flags byte on every link, the same chain type behind a different layout.
+        3 | 0x0103 => Ok(AnyTrustChain::V3(v3::trust_chain(&mut &input[..], root_keys_store, clock)?)),
+        #[cfg(feature = "v4")]
+        4 => Ok(AnyTrustChain::V4(v4::trust_chain(&mut &input[..], root_keys_store, clock).map_err(AnyTrustError::V4)?)),
+        version => Err(AnyTrustError::UnsupportedVersion(version)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_any_should_dispatch_v3 () {
+        use ring::signature::{Ed25519KeyPair, KeyPair};
+
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x01;32]).unwrap();
+        let end_keypair    = Ed25519KeyPair::from_seed_unchecked(&[0x02;32]).unwrap();
+        let root           = v3::PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let end            = v3::PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_sig       = v3::Signature::from(root_keypair.sign(end.as_ref()));
+        let chain          = v3::TrustChain::new(&vec!(root), v3::Root::new(root), &v3::SystemClock)
+            .and_then(|chain| chain.append(v3::Link::new(end, root_sig), &v3::SystemClock))
+            .unwrap();
+        let cb             = chain.as_bytes();
+        let root_key_store = vec!(root);
+        let parsed         = parse_any(&cb, &root_key_store, &v3::SystemClock).unwrap();
+
+        assert_eq!(parsed.version(), 3);
+        assert_eq!(parsed.root_key(), key_bytes(root.as_ref()));
+        assert_eq!(parsed.end_key(),  key_bytes(end.as_ref()));
+        assert_eq!(parsed.as_bytes(), cb);
+        assert_eq!(parsed, AnyTrustChain::V3(chain));
+
+        let sig            = end_keypair.sign(b"test data");
+
+        assert_eq!(parsed.verify_data(sig.as_ref(), b"test data", &v3::SystemClock), Ok(()));
+        assert_eq!(parsed.verify_data(sig.as_ref(), b"tset data", &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::InvalidSignature)));
+        assert_eq!(parse_any(&cb, &vec!(end), &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::NoRootKeyTrust)));
+
+        let distrusted     = vec!(v3::TrustAnchor::new(root).with_distrusted(true));
+
+        assert_eq!(parse_any(&cb, &distrusted, &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::DistrustedAnchor)));
+    }
+
+    #[cfg(feature = "v4")]
+    #[test]
+    fn parse_any_should_dispatch_v4 () {
+        use ring::signature::Ed25519KeyPair;
+        use crate::v4::Signer;
+
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x01;32]).unwrap();
+        let end_keypair    = Ed25519KeyPair::from_seed_unchecked(&[0x02;32]).unwrap();
+        let chain          = v4::TrustChain::new(&vec!(root_keypair.key()), v4::Root::new(root_keypair.key()), &v4::SystemClock)
+            .and_then(|chain| chain.append(v4::issue_link(&root_keypair, 1, end_keypair.key(), None)?, &v4::SystemClock))
+            .unwrap();
+        let cb             = chain.as_bytes();
+        let root_key_store = vec!(root_keypair.key());
+        let parsed         = parse_any(&cb, &root_key_store, &v4::SystemClock).unwrap();
+
+        assert_eq!(parsed.version(), 4);
+        assert_eq!(parsed.end_key(), key_bytes(end_keypair.key().as_ref()));
+        assert_eq!(parsed.as_bytes(), cb);
+        assert_eq!(parsed, AnyTrustChain::V4(chain.clone()));
+
+        let sig            = chain.sign_data(&end_keypair, b"test data").unwrap();
+
+        assert_eq!(parsed.verify_data(sig.as_ref(), b"test data", &v4::SystemClock), Ok(()));
+        assert_eq!(parsed.verify_data(sig.as_ref(), b"tset data", &v4::SystemClock), Err(AnyTrustError::V4(v4::TrustError::InvalidSignature)));
+        assert_eq!(parse_any(&cb, &vec!(end_keypair.key()), &v4::SystemClock), Err(AnyTrustError::V4(v4::TrustError::NoRootKeyTrust)));
+    }
+
+    #[cfg(feature = "v2")]
+    #[test]
+    fn parse_any_should_dispatch_v2 () {
+        use sodiumoxide::crypto::sign::ed25519;
+
+        let (root_pkey, root_skey) = ed25519::gen_keypair();
+        let (end_pkey, end_skey)   = ed25519::gen_keypair();
+        let root_sig  = ed25519::sign_detached(&end_pkey.0, &root_skey);
+        let chain     = v2::TrustChain::two_link_chain(root_pkey, end_pkey, root_sig, Box::new(vec!(root_pkey))).unwrap();
+        let cb        = chain.as_bytes();
+        let root      = v3::PublicKey::from_slice(&root_pkey.0).unwrap();
+        let parsed    = parse_any(&cb, &vec!(root), &v3::SystemClock).unwrap();
+
+        assert_eq!(parsed.version(), 2);
+        assert_eq!(parsed.root_key(), root_pkey.0);
+        assert_eq!(parsed.end_key(),  end_pkey.0);
+        assert_eq!(parsed, AnyTrustChain::V2(chain));
+
+        let sig       = ed25519::sign_detached(b"test data", &end_skey);
+
+        assert_eq!(parsed.verify_data(sig.as_ref(), b"test data", &v3::SystemClock), Ok(()));
+        assert_eq!(
+            parsed.verify_data(sig.as_ref(), b"tset data", &v3::SystemClock),
+            Err(AnyTrustError::V2(v2::TrustError::InvalidSignature)));
+        assert_eq!(
+            parse_any(&cb, &vec!(v3::PublicKey::from_slice(&end_pkey.0).unwrap()), &v3::SystemClock),
+            Err(AnyTrustError::V2(v2::TrustError::NoRootKeyTrust)));
+
+        // the store's anchor terms hold for v2 chains as much as for any other.
+        let shallow    = vec!(v3::TrustAnchor::new(root).with_max_depth(0));
+        let distrusted = vec!(v3::TrustAnchor::new(root).with_distrusted(true));
+
+        assert_eq!(parse_any(&cb, &shallow, &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::PathLengthExceeded)));
+        assert_eq!(parse_any(&cb, &distrusted, &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::DistrustedAnchor)));
+    }
+
+    #[test]
+    fn parse_any_should_reject_unknown_encodings () {
+        let parse = |input: &[u8]| parse_any(input, &Vec::<v3::PublicKey>::new(), &v3::SystemClock);
+
+        assert_eq!(parse(b"TC"),               Err(AnyTrustError::InvalidTrustChain));
+        assert_eq!(parse(b"XY\x00\x03"),     Err(AnyTrustError::InvalidTrustChain));
+        assert_eq!(parse(b"TC\x00\x01"),     Err(AnyTrustError::UnsupportedVersion(1)));
+        assert_eq!(parse(b"TC\x00\x05"),     Err(AnyTrustError::UnsupportedVersion(5)));
+        assert_eq!(parse(b"TC\x01\x00"),     Err(AnyTrustError::UnsupportedVersion(256)));
+    }
+}
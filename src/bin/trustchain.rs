@@ -0,0 +1,605 @@
+use trustchain::v3::{
+    self, Clock, Expiry, FixedClock, NotAfter, NotBefore, PublicKey, Root, SecretKey, Signature, SystemClock,
+    TrustAnchor, TrustChain, TrustError, EXPIRYBYTES, MAXCHAINLINKS, PUBLICKEYBYTES, SIGNATUREBYTES, TC_V3_HEADER, TC_V3_FLAGS_HEADER,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "\
+usage: trustchain <command> [options]
+
+commands:
+  keygen       --out <key> [--passphrase-env <var>]
+  issue        --key <issuer key> --subject <public key hex> --roots <roots> --out <chain>
+               [--chain <chain>] [--not-before <unix> --not-after <unix>] [--at <unix>] [--passphrase-env <var>]
+               [--usage sign-links|sign-data|both] [--max-depth <links>]
+  inspect      <chain>
+  verify       --chain <chain> --roots <roots> [--at <unix>]
+  sign         --chain <chain> --roots <roots> --key <end key> --in <data> [--out <sig>] [--at <unix>]
+               [--passphrase-env <var>]
+  verify-data  --chain <chain> --roots <roots> --in <data> --sig <sig> [--at <unix>]
+
+Keys and signatures are hex, one per line; roots files list one public key per line, '#' starts a comment.
+--roots may also name a directory holding one raw, hex or armored public key per file, with optional
+Name, Not-Before, Not-After, Max-Depth and Distrusted headers.
+Chain files are read either as the binary encoding or as an armored '-----BEGIN TRUST CHAIN-----' block.
+Secret keys are PKCS#8 documents, encrypted when --passphrase-env names a variable holding the passphrase.
+keygen never overwrites an existing file, and on unix creates the key readable by its owner only.
+--usage limits what the subject key may sign; without it the key may sign both links and data.
+--max-depth limits how many links may follow beneath the subject key, 1 allowing end keys only.
+";
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Io(String, io::Error),
+    Trust(TrustError),
+}
+
+impl From<TrustError> for CliError {
+    fn from (err: TrustError) -> Self { CliError::Trust(err) }
+}
+
+impl CliError {
+    //--- 2 and 3 for usage and I/O problems, then one code per TrustError variant from 10 up
+    fn exit_code (&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Io(_, _) => 3,
+            CliError::Trust(err) => match err {
+                TrustError::NoRootKeyTrust              => 10,
+                TrustError::InvalidSignature            => 11,
+                TrustError::InvalidExpiry               => 12,
+                TrustError::MaxChainLengthExceeded      => 13,
+                TrustError::InvalidTrustChain           => 14,
+                TrustError::InvalidRevocationList       => 15,
+                TrustError::RevokedKey(_)               => 16,
+                TrustError::KeyGenerationFailed         => 17,
+                TrustError::SigningFailed               => 18,
+                TrustError::InvalidSecretKey            => 19,
+                TrustError::KeyDecryptionFailed         => 20,
+                TrustError::UnexpectedEof               => 21,
+                TrustError::TrailingData                => 22,
+                TrustError::ReadFailed(_)               => 23,
+                TrustError::InvalidArmor                => 24,
+                TrustError::InvalidKeyEncoding          => 25,
+                TrustError::DistrustedAnchor            => 26,
+                TrustError::PathLengthExceeded          => 27,
+                TrustError::InvalidAttributes           => 28,
+                TrustError::UnknownCriticalAttribute(_) => 29,
+                TrustError::LinkSigningNotPermitted     => 30,
+                TrustError::DataSigningNotPermitted     => 31,
+            },
+        }
+    }
+}
+
+fn main () {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let stdout = io::stdout();
+
+    if let Err(err) = run(&args, &mut stdout.lock()) {
+        match &err {
+            CliError::Usage(msg)     => eprint!("trustchain: {}\n\n{}", msg, USAGE),
+            CliError::Io(path, e)    => eprintln!("trustchain: {}: {}", path, e),
+            CliError::Trust(e)       => eprintln!("trustchain: {:?}", e),
+        }
+        process::exit(err.exit_code());
+    }
+}
+
+fn run (args: &[String], out: &mut dyn Write) -> Result<(), CliError> {
+    let (command, rest) = args.split_first().ok_or_else(|| CliError::Usage("missing command".into()))?;
+    let opts = Options::parse(rest)?;
+
+    match command.as_str() {
+        "keygen"      => keygen(&opts, out),
+        "issue"       => issue(&opts, out),
+        "inspect"     => inspect(&opts, out),
+        "verify"      => verify(&opts, out),
+        "sign"        => sign(&opts, out),
+        "verify-data" => verify_data(&opts, out),
+        "help" | "--help" | "-h" => write(out, USAGE),
+        other         => Err(CliError::Usage(format!("unknown command '{}'", other))),
+    }
+}
+
+//--- `--name value` pairs plus bare positional arguments
+struct Options {
+    named: HashMap<String, String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse (args: &[String]) -> Result<Options, CliError> {
+        let mut named = HashMap::new();
+        let mut positional = Vec::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                let value = args.next().ok_or_else(|| CliError::Usage(format!("--{} needs a value", name)))?;
+                named.insert(name.to_string(), value.clone());
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Options { named, positional })
+    }
+
+    fn get (&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(String::as_str)
+    }
+
+    fn required (&self, name: &str) -> Result<&str, CliError> {
+        self.get(name).ok_or_else(|| CliError::Usage(format!("missing --{}", name)))
+    }
+
+    fn number (&self, name: &str) -> Result<Option<u64>, CliError> {
+        self.get(name)
+            .map(|v| v.parse::<u64>().map_err(|_| CliError::Usage(format!("--{} expects a unix timestamp", name))))
+            .transpose()
+    }
+
+    fn clock (&self) -> Result<Box<dyn Clock>, CliError> {
+        Ok(match self.number("at")? {
+            Some(at) => Box::new(FixedClock::new(at)),
+            None     => Box::new(SystemClock),
+        })
+    }
+
+    fn passphrase (&self) -> Result<Option<String>, CliError> {
+        self.get("passphrase-env")
+            .map(|var| std::env::var(var).map_err(|_| CliError::Usage(format!("environment variable {} is not set", var))))
+            .transpose()
+    }
+}
+
+fn keygen (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let path = opts.required("out")?;
+    let key  = SecretKey::generate()?;
+    let der  = match opts.passphrase()? {
+        Some(passphrase) => key.to_encrypted_pkcs8(passphrase.as_bytes())?,
+        None             => key.to_pkcs8().to_vec(),
+    };
+    write_secret_file(path, &der)?;
+    write(out, &format!("{}\n", hex(key.public_key().as_ref())))
+}
+
+fn issue (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let issuer  = read_secret_key(opts)?;
+    let subject = parse_key(opts.required("subject")?)?;
+    let roots   = read_roots(opts.required("roots")?)?;
+    let clock   = opts.clock()?;
+    let expiry  = match (opts.number("not-before")?, opts.number("not-after")?) {
+        (Some(nb), Some(na)) => Some(Expiry::new(NotBefore(nb), NotAfter(na))?),
+        (None, None)         => None,
+        _                    => return Err(CliError::Usage("--not-before and --not-after go together".into())),
+    };
+    let mut attributes = v3::Attributes::new();
+    if let Some(usage) = opts.get("usage") {
+        attributes = attributes.with_key_usage(parse_usage(usage)?)?;
+    }
+    if let Some(depth) = opts.get("max-depth") {
+        let depth = depth.parse::<u8>().map_err(|_| CliError::Usage("--max-depth expects a number of links".into()))?;
+        attributes = attributes.with_max_depth(depth)?;
+    }
+
+    let chain = match opts.get("chain") {
+        Some(path) => read_chain(path, &roots, clock.as_ref())?,
+        None       => TrustChain::new(&roots, Root::new(issuer.public_key()), clock.as_ref())?,
+    };
+    let chain = chain.append(v3::issue_link_with_attributes(&issuer, subject, expiry, attributes)?, clock.as_ref())?;
+
+    write_file(opts.required("out")?, &chain.as_bytes())?;
+    write(out, &format!("{} links, end key {}\n", chain.len(), hex(chain.last().key().as_ref())))
+}
+
+//--- Decodes the layout without verifying anything, so broken chains can be looked at too
+fn inspect (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let path  = opts.positional.first().map(String::as_str)
+        .or_else(|| opts.get("chain"))
+        .ok_or_else(|| CliError::Usage("missing chain file".into()))?;
+    let bytes = read_file(path)?;
+    let mut input = bytes.as_slice();
+
+    let header = take(&mut input, TC_V3_HEADER.len())?;
+    let flagged = match header {
+        h if h == TC_V3_HEADER       => false,
+        h if h == TC_V3_FLAGS_HEADER => true,
+        _                            => return Err(TrustError::InvalidTrustChain.into()),
+    };
+    write(out, &format!("header      {} (TC v3{})\n", hex(header), if flagged { ", link flags" } else { "" }))?;
+
+    let len = take(&mut input, 1)?[0];
+    write(out, &format!("length      {}{}\n", len, if len > MAXCHAINLINKS { " (exceeds maximum)" } else { "" }))?;
+    let root = take(&mut input, PUBLICKEYBYTES)?;
+    write(out, &format!("root        {}\n", hex(root)))?;
+    write(out, &format!("fingerprint {}\n", fingerprint(root)))?;
+
+    for i in 1..len {
+        let key = take(&mut input, PUBLICKEYBYTES)?;
+        write(out, &format!("link {}      key        {}\n", i, hex(key)))?;
+        write(out, &format!("            fingerprint {}\n", fingerprint(key)))?;
+        let flags = if flagged { take(&mut input, 1)?[0] } else { 0 };
+        if flagged {
+            write(out, &format!("            flags      {:#04x}\n", flags))?;
+        }
+        if flags & v3::LINK_FLAG_EXPIRY != 0 {
+            let expiry = Expiry::from_slice(take(&mut input, EXPIRYBYTES)?).ok_or(TrustError::InvalidTrustChain)?;
+            write(out, &format!("            not-before {}\n", expiry.not_before().0))?;
+            write(out, &format!("            not-after  {}\n", expiry.not_after().0))?;
+        }
+        if flags & v3::LINK_FLAG_ATTRIBUTES != 0 {
+            let len = take(&mut input, 2)?;
+            let attributes = v3::Attributes::from_slice(take(&mut input, u16::from_be_bytes([len[0], len[1]]) as usize)?)?;
+            inspect_attributes(&attributes, out)?;
+        }
+        write(out, &format!("            signature  {}\n", hex(take(&mut input, SIGNATUREBYTES)?)))?;
+    }
+
+    if !input.is_empty() {
+        write(out, &format!("trailing    {} bytes\n", input.len()))?;
+    }
+    Ok(())
+}
+
+fn inspect_attributes (attributes: &v3::Attributes, out: &mut dyn Write) -> Result<(), CliError> {
+    if let Some(usage) = attributes.key_usage() {
+        write(out, &format!("            key-usage  {}\n", usage))?;
+    }
+    if let Some(depth) = attributes.max_depth() {
+        write(out, &format!("            max-depth  {}\n", depth))?;
+    }
+    if let Some(label) = attributes.label() {
+        write(out, &format!("            label      {}\n", label))?;
+    }
+    for a in attributes.iter().filter(|a| !a.is_standard()) {
+        let critical = if a.is_critical() { " (critical)" } else { "" };
+        write(out, &format!("            attribute  {:#04x}{} {}\n", a.id(), critical, hex(a.value())))?;
+    }
+    Ok(())
+}
+
+fn verify (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let roots = read_roots(opts.required("roots")?)?;
+    let chain = read_chain(opts.required("chain")?, &roots, opts.clock()?.as_ref())?;
+    let anchor = chain.anchor().name().map(|name| format!(", anchor {}", name)).unwrap_or_default();
+    write(out, &format!("ok: {} links, end key {}{}\n", chain.len(), hex(chain.last().key().as_ref()), anchor))
+}
+
+fn sign (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let roots = read_roots(opts.required("roots")?)?;
+    let chain = read_chain(opts.required("chain")?, &roots, opts.clock()?.as_ref())?;
+    let key   = read_secret_key(opts)?;
+    let data  = read_file(opts.required("in")?)?;
+    let sig   = format!("{}\n", hex(chain.sign_data(&key, &data)?.as_ref()));
+
+    match opts.get("out") {
+        Some(path) => write_file(path, sig.as_bytes()),
+        None       => write(out, &sig),
+    }
+}
+
+fn verify_data (opts: &Options, out: &mut dyn Write) -> Result<(), CliError> {
+    let clock = opts.clock()?;
+    let roots = read_roots(opts.required("roots")?)?;
+    let chain = read_chain(opts.required("chain")?, &roots, clock.as_ref())?;
+    let data  = read_file(opts.required("in")?)?;
+    let sig   = read_text(opts.required("sig")?)?;
+    let sig   = unhex(sig.trim()).and_then(|b| Signature::from_slice(&b)).ok_or(TrustError::InvalidSignature)?;
+
+    chain.verify_data(&sig, &data, clock.as_ref())?;
+    write(out, "ok\n")
+}
+
+//--- Either the binary encoding or an armored block, told apart by the armor's BEGIN line
+fn read_chain (path: &str, roots: &Vec<TrustAnchor>, clock: &dyn Clock) -> Result<TrustChain, CliError> {
+    let bytes = read_file(path)?;
+    match std::str::from_utf8(&bytes) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN ") => Ok(v3::armored_trust_chain(text, roots, clock)?),
+        _ => Ok(v3::trust_chain_strict(&mut bytes.as_slice(), roots, clock)?),
+    }
+}
+
+//--- A roots file, or a roots.d style directory of anchor files whose unreadable entries are warned about
+fn read_roots (path: &str) -> Result<Vec<TrustAnchor>, CliError> {
+    if std::path::Path::new(path).is_dir() {
+        let store = v3::DirectoryKeysStore::open(path).map_err(|e| CliError::Io(path.to_string(), e))?;
+        for failure in store.failures() {
+            eprintln!("trustchain: skipping {}: {:?}", failure.path.display(), failure.error);
+        }
+        return Ok(store.anchors().iter().map(|a| a.anchor.clone()).collect())
+    }
+    read_text(path)?
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_key(line).map(TrustAnchor::new))
+        .collect()
+}
+
+fn read_secret_key (opts: &Options) -> Result<SecretKey, CliError> {
+    let der = read_file(opts.required("key")?)?;
+    Ok(match opts.passphrase()? {
+        Some(passphrase) => SecretKey::from_encrypted_pkcs8(&der, passphrase.as_bytes())?,
+        None             => SecretKey::from_pkcs8(&der)?,
+    })
+}
+
+fn parse_key (s: &str) -> Result<PublicKey, CliError> {
+    s.parse().map_err(|_| CliError::Usage(format!("'{}' is not a hex encoded public key", s.trim())))
+}
+
+fn take <'a> (input: &mut &'a [u8], n: usize) -> Result<&'a [u8], CliError> {
+    if input.len() < n { return Err(TrustError::UnexpectedEof.into()) }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Ok(head)
+}
+
+fn read_file (path: &str) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+fn read_text (path: &str) -> Result<String, CliError> {
+    fs::read_to_string(path).map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+fn write_file (path: &str, bytes: &[u8]) -> Result<(), CliError> {
+    fs::write(path, bytes).map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+// never overwrites, and on unix the key is readable by its owner alone from the moment it exists.
+fn write_secret_file (path: &str, bytes: &[u8]) -> Result<(), CliError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| CliError::Io(path.to_string(), e))
+}
+
+fn write (out: &mut dyn Write, s: &str) -> Result<(), CliError> {
+    out.write_all(s.as_bytes()).map_err(|e| CliError::Io("<stdout>".to_string(), e))
+}
+
+// safe because callers only pass slices taken at PUBLICKEYBYTES.
+fn fingerprint (key: &[u8]) -> v3::Fingerprint {
+    PublicKey::from_slice(key).unwrap().fingerprint()
+}
+
+fn parse_usage (usage: &str) -> Result<v3::KeyUsage, CliError> {
+    match usage {
+        "sign-links" => Ok(v3::KeyUsage::SIGN_LINKS),
+        "sign-data"  => Ok(v3::KeyUsage::SIGN_DATA),
+        "both"       => Ok(v3::KeyUsage::BOTH),
+        other        => Err(CliError::Usage(format!("unknown --usage '{}'", other))),
+    }
+}
+
+fn hex (bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex (s: &str) -> Option<Vec<u8>> {
+    if !s.is_ascii() || s.len() % 2 == 1 { return None }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new (name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("trustchain-cli-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn path (&self, file: &str) -> String {
+            self.0.join(file).to_str().unwrap().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop (&mut self) { let _ = fs::remove_dir_all(&self.0); }
+    }
+
+    fn cli (args: &[&str]) -> Result<String, CliError> {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let mut out = Vec::new();
+        run(&args, &mut out).map(|_| String::from_utf8(out).unwrap())
+    }
+
+    fn code (res: Result<String, CliError>) -> i32 {
+        res.map(|_| 0).unwrap_or_else(|e| e.exit_code())
+    }
+
+    #[test]
+    fn cli_should_mint_inspect_and_verify_a_chain () {
+        let dir   = TempDir::new("mint");
+        let (root, intr, end, roots, chain) = (dir.path("root.pk8"), dir.path("intr.pk8"), dir.path("end.pk8"), dir.path("roots"), dir.path("chain"));
+
+        let root_pub = cli(&["keygen", "--out", &root]).unwrap();
+        let intr_pub = cli(&["keygen", "--out", &intr]).unwrap();
+        let end_pub  = cli(&["keygen", "--out", &end]).unwrap();
+        fs::write(&roots, format!("# production roots\n{}", root_pub)).unwrap();
+
+        cli(&["issue", "--key", &root, "--subject", intr_pub.trim(), "--roots", &roots, "--out", &chain]).unwrap();
+        cli(&["issue", "--key", &intr, "--subject", end_pub.trim(), "--roots", &roots, "--chain", &chain, "--out", &chain,
+              "--not-before", "1000", "--not-after", "2000", "--at", "1500"]).unwrap();
+
+        let inspected = cli(&["inspect", &chain]).unwrap();
+
+        assert!(inspected.contains("length      3"));
+        assert!(inspected.contains(&format!("root        {}", root_pub.trim())));
+        assert!(inspected.contains(&format!("link 2      key        {}", end_pub.trim())));
+        assert!(inspected.contains("not-after  2000"));
+        assert!(inspected.contains(&format!("fingerprint {}", parse_key(&end_pub).unwrap().fingerprint())));
+
+        assert_eq!(cli(&["verify", "--chain", &chain, "--roots", &roots, "--at", "1500"]).unwrap(),
+                   format!("ok: 3 links, end key {}", end_pub));
+        assert_eq!(code(cli(&["verify", "--chain", &chain, "--roots", &roots, "--at", "2500"])), 12);
+        assert_eq!(code(cli(&["verify", "--chain", &chain, "--roots", &roots])), 12);
+
+        let armored = dir.path("chain.asc");
+        let bytes   = fs::read(&chain).unwrap();
+        fs::write(&armored, v3::Armor::new(v3::TRUST_CHAIN_LABEL, bytes).header("Comment", "test").unwrap().to_string()).unwrap();
+
+        assert!(cli(&["verify", "--chain", &armored, "--roots", &roots, "--at", "1500"]).is_ok());
+        assert_eq!(code(cli(&["verify", "--chain", &armored, "--roots", &roots, "--at", "2500"])), 12);
+
+        fs::write(&armored, "-----BEGIN TRUST CHAIN-----\n!!\n-----END TRUST CHAIN-----\n").unwrap();
+
+        assert_eq!(code(cli(&["verify", "--chain", &armored, "--roots", &roots, "--at", "1500"])), 24);
+
+        let roots_d = dir.path("roots.d");
+        fs::create_dir(&roots_d).unwrap();
+        fs::write(dir.path("roots.d/root.key"), format!("Name: test root\n{}", root_pub)).unwrap();
+        fs::write(dir.path("roots.d/broken.key"), "not a key").unwrap();
+
+        assert_eq!(cli(&["verify", "--chain", &chain, "--roots", &roots_d, "--at", "1500"]).unwrap(),
+                   format!("ok: 3 links, end key {}, anchor test root\n", end_pub.trim()));
+
+        fs::write(dir.path("roots.d/root.key"), format!("Max-Depth: 1\n{}", root_pub)).unwrap();
+
+        assert_eq!(code(cli(&["verify", "--chain", &chain, "--roots", &roots_d, "--at", "1500"])), 27);
+
+        fs::write(dir.path("roots.d/root.key"), format!("Distrusted: yes\n{}", root_pub)).unwrap();
+
+        assert_eq!(code(cli(&["verify", "--chain", &chain, "--roots", &roots_d, "--at", "1500"])), 26);
+
+        fs::write(&roots, &intr_pub).unwrap();
+
+        assert_eq!(code(cli(&["verify", "--chain", &chain, "--roots", &roots, "--at", "1500"])), 10);
+    }
+
+    #[test]
+    fn keygen_should_write_owner_only_keys_and_never_overwrite () {
+        let dir   = TempDir::new("keygen");
+        let key   = dir.path("key.pk8");
+
+        cli(&["keygen", "--out", &key]).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&key).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let before = fs::read(&key).unwrap();
+
+        assert_eq!(code(cli(&["keygen", "--out", &key])), 3);
+        assert_eq!(fs::read(&key).unwrap(), before);
+    }
+
+    #[test]
+    fn cli_should_enforce_key_usage () {
+        let dir   = TempDir::new("key-usage");
+        let (root, intr, end, roots, chain, data, sig) = (dir.path("root.pk8"), dir.path("intr.pk8"), dir.path("end.pk8"), dir.path("roots"), dir.path("chain"), dir.path("data"), dir.path("sig"));
+
+        let root_pub = cli(&["keygen", "--out", &root]).unwrap();
+        let intr_pub = cli(&["keygen", "--out", &intr]).unwrap();
+        let end_pub  = cli(&["keygen", "--out", &end]).unwrap();
+        fs::write(&roots, &root_pub).unwrap();
+        fs::write(&data, b"test data").unwrap();
+
+        assert_eq!(code(cli(&["issue", "--key", &root, "--subject", intr_pub.trim(), "--roots", &roots, "--out", &chain, "--usage", "sign-everything"])), 2);
+
+        cli(&["issue", "--key", &root, "--subject", intr_pub.trim(), "--roots", &roots, "--out", &chain, "--usage", "sign-links"]).unwrap();
+
+        assert!(cli(&["inspect", &chain]).unwrap().contains("key-usage  sign-links"));
+        assert_eq!(code(cli(&["sign", "--chain", &chain, "--roots", &roots, "--key", &intr, "--in", &data])), 31);
+
+        cli(&["issue", "--key", &intr, "--subject", end_pub.trim(), "--roots", &roots, "--chain", &chain, "--out", &chain, "--usage", "sign-data"]).unwrap();
+        cli(&["sign", "--chain", &chain, "--roots", &roots, "--key", &end, "--in", &data, "--out", &sig]).unwrap();
+
+        assert_eq!(cli(&["verify-data", "--chain", &chain, "--roots", &roots, "--in", &data, "--sig", &sig]).unwrap(), "ok\n");
+        assert_eq!(code(cli(&["issue", "--key", &end, "--subject", intr_pub.trim(), "--roots", &roots, "--chain", &chain, "--out", &chain])), 30);
+    }
+
+    #[test]
+    fn cli_should_enforce_max_depth () {
+        let dir   = TempDir::new("depth");
+        let (root, team, intr, roots, chain) = (dir.path("root.pk8"), dir.path("team.pk8"), dir.path("intr.pk8"), dir.path("roots"), dir.path("chain"));
+
+        let root_pub = cli(&["keygen", "--out", &root]).unwrap();
+        let team_pub = cli(&["keygen", "--out", &team]).unwrap();
+        let intr_pub = cli(&["keygen", "--out", &intr]).unwrap();
+        fs::write(&roots, &root_pub).unwrap();
+
+        assert_eq!(code(cli(&["issue", "--key", &root, "--subject", team_pub.trim(), "--roots", &roots, "--out", &chain, "--max-depth", "-1"])), 2);
+
+        cli(&["issue", "--key", &root, "--subject", team_pub.trim(), "--roots", &roots, "--out", &chain, "--max-depth", "1"]).unwrap();
+
+        assert!(cli(&["inspect", &chain]).unwrap().contains("max-depth  1"));
+
+        let end  = dir.path("end");
+        cli(&["issue", "--key", &team, "--subject", intr_pub.trim(), "--roots", &roots, "--chain", &chain, "--out", &end]).unwrap();
+
+        assert_eq!(code(cli(&["issue", "--key", &intr, "--subject", team_pub.trim(), "--roots", &roots, "--chain", &end, "--out", &end])), 27);
+    }
+
+    #[test]
+    fn cli_should_sign_and_verify_data () {
+        let dir   = TempDir::new("sign");
+        let (root, end, roots, chain, data, sig) = (dir.path("root.pk8"), dir.path("end.pk8"), dir.path("roots"), dir.path("chain"), dir.path("data"), dir.path("sig"));
+        let windowed = dir.path("windowed");
+
+        std::env::set_var("TRUSTCHAIN_CLI_TEST_PASSPHRASE", "correct horse");
+        let root_pub = cli(&["keygen", "--out", &root]).unwrap();
+        let end_pub  = cli(&["keygen", "--out", &end, "--passphrase-env", "TRUSTCHAIN_CLI_TEST_PASSPHRASE"]).unwrap();
+        fs::write(&roots, &root_pub).unwrap();
+        fs::write(&data, b"test data").unwrap();
+
+        cli(&["issue", "--key", &root, "--subject", end_pub.trim(), "--roots", &roots, "--out", &chain]).unwrap();
+
+        assert_eq!(code(cli(&["sign", "--chain", &chain, "--roots", &roots, "--key", &end, "--in", &data])), 19);
+        assert_eq!(code(cli(&["sign", "--chain", &chain, "--roots", &roots, "--key", &root, "--in", &data])), 18);
+
+        cli(&["sign", "--chain", &chain, "--roots", &roots, "--key", &end, "--in", &data, "--out", &sig,
+              "--passphrase-env", "TRUSTCHAIN_CLI_TEST_PASSPHRASE"]).unwrap();
+
+        assert_eq!(cli(&["verify-data", "--chain", &chain, "--roots", &roots, "--in", &data, "--sig", &sig]).unwrap(), "ok\n");
+
+        fs::write(&data, b"tset data").unwrap();
+
+        assert_eq!(code(cli(&["verify-data", "--chain", &chain, "--roots", &roots, "--in", &data, "--sig", &sig])), 11);
+
+        // a chain with a validity window signs at the time given, as it verifies.
+        cli(&["issue", "--key", &root, "--subject", end_pub.trim(), "--roots", &roots, "--out", &windowed,
+              "--not-before", "1000", "--not-after", "2000", "--at", "1500"]).unwrap();
+        let sign_at = |at: &str| cli(&["sign", "--chain", &windowed, "--roots", &roots, "--key", &end, "--in", &data, "--out", &sig,
+                                       "--at", at, "--passphrase-env", "TRUSTCHAIN_CLI_TEST_PASSPHRASE"]);
+
+        assert!(sign_at("1500").is_ok());
+        assert_eq!(cli(&["verify-data", "--chain", &windowed, "--roots", &roots, "--in", &data, "--sig", &sig, "--at", "1500"]).unwrap(), "ok\n");
+        assert_eq!(code(sign_at("2500")), 12);
+    }
+
+    #[test]
+    fn cli_should_report_usage_and_malformed_input () {
+        let dir   = TempDir::new("usage");
+        let chain = dir.path("chain");
+        fs::write(&chain, b"TC\x00\x03\x02").unwrap();
+
+        assert_eq!(code(cli(&[])), 2);
+        assert_eq!(code(cli(&["mint"])), 2);
+        assert_eq!(code(cli(&["verify", "--chain"])), 2);
+        assert_eq!(code(cli(&["inspect", &dir.path("missing")])), 3);
+        assert_eq!(code(cli(&["inspect", &chain])), 21);
+
+        fs::write(&chain, b"TC\x00\x02\x02").unwrap();
+
+        assert_eq!(code(cli(&["inspect", &chain])), 14);
+    }
+}
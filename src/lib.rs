@@ -7,3 +7,15 @@ pub mod v2;
 
 #[cfg(feature = "v3")]
 pub mod v3;
+
+#[cfg(feature = "v4")]
+pub mod v4;
+
+// parse_any checks every version against v3 root key stores and clocks, so it needs v3 whatever else is enabled.
+#[cfg(feature = "v3")]
+mod any;
+#[cfg(feature = "v3")]
+pub use any::*;
+
+#[cfg(all(feature = "v2", feature = "v3"))]
+pub mod migrate;
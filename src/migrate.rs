@@ -0,0 +1,127 @@
+use sodiumoxide::crypto::sign::ed25519;
+use crate::v2;
+use crate::v3;
+use crate::any::AnyTrustError;
+
+// Both versions sign the raw 32-byte child key with Ed25519, and a v3 link without a validity
+// window signs exactly that, so links carry over between the two without re-signing.
+
+//--- Re-issues a verified v2 chain as the equivalent v3 chain, verifying it again against a v3 root store
+pub fn v2_to_v3 <'ks> (
+    chain: &v2::TrustChain,
+    root_keys_store: &'ks (dyn v3::RootKeysStore<'ks> + 'ks),
+    clock: &dyn v3::Clock ) -> Result<v3::TrustChain, AnyTrustError> {
+
+    let root = v3::PublicKey::from_slice(&chain.root_key().0).ok_or(AnyTrustError::InvalidTrustChain)?;
+    let mut migrated = v3::TrustChain::new(root_keys_store, v3::Root::new(root), clock)?;
+
+    for (key, sig) in chain.links() {
+        let key = v3::PublicKey::from_slice(&key.0).ok_or(AnyTrustError::InvalidTrustChain)?;
+        let sig = v3::Signature::from_slice(sig.as_ref()).ok_or(AnyTrustError::InvalidTrustChain)?;
+        migrated = migrated.append(v3::Link::new(key, sig), clock)?;
+    }
+    Ok(migrated)
+}
+
+//--- The reverse, possible only when no link of the v3 chain carries a validity window or attributes
+pub fn v3_to_v2 (
+    chain: &v3::TrustChain,
+    root_keys_store: Box<dyn v2::RootKeysStore> ) -> Result<v2::TrustChain, AnyTrustError> {
+
+    if chain.iter().any(|l| l.expiry().is_some() || !l.attributes().is_empty()) {
+        return Err(AnyTrustError::NotRepresentable)
+    }
+
+    let root = ed25519::PublicKey::from_slice(chain.first().key().as_ref()).ok_or(AnyTrustError::InvalidTrustChain)?;
+    let mut migrated = v2::TrustChain::root_only_chain(root, root_keys_store)?;
+
+    for l in chain.iter().skip(1) {
+        let key = ed25519::PublicKey::from_slice(l.key().as_ref()).ok_or(AnyTrustError::InvalidTrustChain)?;
+        let sig = l.signature()
+            .and_then(|sig| ed25519::Signature::from_bytes(sig.as_ref()).ok())
+            .ok_or(AnyTrustError::InvalidTrustChain)?;
+        migrated = migrated.append(key, sig)?;
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn v2_key (keypair: &Ed25519KeyPair) -> ed25519::PublicKey {
+        ed25519::PublicKey::from_slice(keypair.public_key().as_ref()).unwrap()
+    }
+
+    fn v3_key (pkey: &ed25519::PublicKey) -> v3::PublicKey {
+        v3::PublicKey::from_slice(&pkey.0).unwrap()
+    }
+
+    #[test]
+    fn v2_chain_should_migrate_to_v3_and_back () {
+        let (root_pkey, root_skey)   = ed25519::gen_keypair();
+        let (intr_pkey, intr_skey)   = ed25519::gen_keypair();
+        let (end_pkey, end_skey)     = ed25519::gen_keypair();
+        let root_sig = ed25519::sign_detached(&intr_pkey.0, &root_skey);
+        let intr_sig = ed25519::sign_detached(&end_pkey.0, &intr_skey);
+        let v2_chain = v2::TrustChain::three_link_chain(
+            root_pkey, intr_pkey, end_pkey, root_sig, intr_sig, Box::new(vec!(root_pkey))).unwrap();
+
+        let v3_store = vec!(v3_key(&root_pkey));
+        let v3_chain = v2_to_v3(&v2_chain, &v3_store, &v3::SystemClock).unwrap();
+
+        assert_eq!(v3_chain.len(), 3);
+        assert_eq!(v3_chain.first().key(), v3_key(&root_pkey));
+        assert_eq!(v3_chain.last().key(),  v3_key(&end_pkey));
+
+        // data signed by the sodiumoxide end key verifies through the ring backed v3 chain.
+        let data_sig = ed25519::sign_detached(b"test data", &end_skey);
+        let v3_sig   = v3::Signature::from_slice(data_sig.as_ref()).unwrap();
+
+        assert_eq!(v3_chain.verify_data(&v3_sig, b"test data", &v3::SystemClock), Ok(()));
+
+        let round_trip = v3_to_v2(&v3_chain, Box::new(vec!(root_pkey))).unwrap();
+
+        assert_eq!(round_trip, v2_chain);
+        assert_eq!(v2_to_v3(&v2_chain, &vec!(v3_key(&intr_pkey)), &v3::SystemClock), Err(AnyTrustError::V3(v3::TrustError::NoRootKeyTrust)));
+    }
+
+    #[test]
+    fn v3_chain_should_migrate_to_v2_and_back () {
+        let root_keypair = Ed25519KeyPair::from_seed_unchecked(&[0x01;32]).unwrap();
+        let end_keypair  = Ed25519KeyPair::from_seed_unchecked(&[0x02;32]).unwrap();
+        let root         = v3_key(&v2_key(&root_keypair));
+        let end          = v3_key(&v2_key(&end_keypair));
+        let link1        = v3::Link::new(end, v3::Signature::from(root_keypair.sign(end.as_ref())));
+        let v3_store     = vec!(root);
+        let v3_chain     = v3::TrustChain::new(&v3_store, v3::Root::new(root), &v3::SystemClock)
+            .and_then(|chain| chain.append(link1, &v3::SystemClock))
+            .unwrap();
+
+        let v2_chain     = v3_to_v2(&v3_chain, Box::new(vec!(v2_key(&root_keypair)))).unwrap();
+
+        // data signed by the ring end key verifies through the sodiumoxide backed v2 chain.
+        let data_sig     = ed25519::Signature::from_bytes(end_keypair.sign(b"test data").as_ref()).unwrap();
+
+        assert_eq!(v2_chain.verify_data(&data_sig, b"test data"), Ok(()));
+        assert_eq!(v2_chain.end_key(), v2_key(&end_keypair));
+        assert_eq!(v2_to_v3(&v2_chain, &v3_store, &v3::SystemClock), Ok(v3_chain));
+    }
+
+    #[test]
+    fn windowed_v3_chain_should_not_migrate_to_v2 () {
+        let root_keypair = Ed25519KeyPair::from_seed_unchecked(&[0x01;32]).unwrap();
+        let end_keypair  = Ed25519KeyPair::from_seed_unchecked(&[0x02;32]).unwrap();
+        let root         = v3_key(&v2_key(&root_keypair));
+        let end          = v3_key(&v2_key(&end_keypair));
+        let expiry       = v3::Expiry::new(v3::NotBefore(0), v3::NotAfter(u64::MAX)).unwrap();
+        let signed_data  = v3::Link::signed_data(&end, Some(&expiry));
+        let link1        = v3::Link::with_expiry(end, expiry, v3::Signature::from(root_keypair.sign(&signed_data)));
+        let v3_chain     = v3::TrustChain::new(&vec!(root), v3::Root::new(root), &v3::SystemClock)
+            .and_then(|chain| chain.append(link1, &v3::SystemClock))
+            .unwrap();
+
+        assert_eq!(v3_to_v2(&v3_chain, Box::new(vec!(v2_key(&root_keypair)))), Err(AnyTrustError::NotRepresentable));
+    }
+}
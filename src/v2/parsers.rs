@@ -1,11 +1,13 @@
-use sodiumoxide::crypto::sign::ed25519::{self,PublicKey,Signature};
+use sodiumoxide::crypto::sign::ed25519::{PublicKey,Signature};
+#[cfg(test)]
+use sodiumoxide::crypto::sign::ed25519;
 use nom::{be_u8,be_u16};
 use super::error::TrustError;
 use super::trust_chain::{RootKeysStore,TrustChain,PUBLICKEYBYTES,SIGNATUREBYTES,MAXCHAINLINKS};
 
 named!(signature<Signature>, do_parse!(
     bytes: take!(SIGNATUREBYTES) >>
-    sig: expr_opt!(Signature::from_slice(bytes)) >>
+    sig: expr_opt!(Signature::from_bytes(bytes).ok()) >>
     (sig)
 ));
 
@@ -19,7 +21,7 @@ named!(public_key<PublicKey>, do_parse!(
 
 fn chain_for (
     untrusted_root_key: PublicKey,
-    root_keys_store:Box<RootKeysStore>,
+    root_keys_store:Box<dyn RootKeysStore>,
     links:Vec<(PublicKey,Signature)>) -> Result<TrustChain,TrustError> {
     match links.len() {
         0 => TrustChain::root_only_chain(
@@ -62,7 +64,7 @@ fn chain_for (
     }
 }
 
-named_args!(pub trust_chain(root_keys:Box<RootKeysStore>)<TrustChain>, do_parse!(
+named_args!(pub trust_chain(root_keys:Box<dyn RootKeysStore>)<TrustChain>, do_parse!(
     _tag: tag!("TC") >>
     _ver: verify!(be_u16, |ver:u16| ver == 2) >>
     root: public_key >>
@@ -72,6 +74,7 @@ named_args!(pub trust_chain(root_keys:Box<RootKeysStore>)<TrustChain>, do_parse!
     (chain)
 ));
 
+#[cfg(test)]
 fn assert_arr_elm_eq (lft: &[u8], lft_off:usize, rgt:&[u8], rgt_off:usize, count:usize) {
     let mut i = 0;
     while i < count {
@@ -80,10 +83,12 @@ fn assert_arr_elm_eq (lft: &[u8], lft_off:usize, rgt:&[u8], rgt_off:usize, count
     }
 }
 
+#[cfg(test)]
 fn assert_key (lft: &[u8], lft_off:usize, rgt:&[u8], rgt_off:usize) {
     assert_arr_elm_eq(lft, lft_off, rgt, rgt_off, PUBLICKEYBYTES)
 }
 
+#[cfg(test)]
 fn assert_sig (lft: &[u8], lft_off:usize, rgt:&[u8], rgt_off:usize) {
     assert_arr_elm_eq(lft, lft_off, rgt, rgt_off, SIGNATUREBYTES)
 }
@@ -156,7 +161,7 @@ fn two_link_chain_end_to_end_test () {
     assert_key(&cb,4, &root_pkey.0,0);// root public key, 32 bytes
     assert_eq!(cb[36], 0x01);// subsequent chain length
     assert_key(&cb,37, &end_pkey.0,0);
-    assert_sig(&cb,69, &root_sig.0,0);
+    assert_sig(&cb,69, root_sig.as_ref(),0);
 
     let parsed_chain_res = trust_chain(&cb, root_key_store);
 
@@ -204,9 +209,9 @@ fn three_link_chain_end_to_end_test () {
     assert_key(&cb,4, &root_pkey.0,0);// root public key, 32 bytes
     assert_eq!(cb[36], 0x02);// subsequent chain length
     assert_key(&cb,37, &intr_pkey.0,0);
-    assert_sig(&cb,69, &root_sig.0,0);
+    assert_sig(&cb,69, root_sig.as_ref(),0);
     assert_key(&cb,133, &end_pkey.0,0);
-    assert_sig(&cb,165, &intr_sig.0,0);
+    assert_sig(&cb,165, intr_sig.as_ref(),0);
 
     let parsed_chain_res = trust_chain(&cb, root_key_store);
 
@@ -258,11 +263,11 @@ fn four_link_chain_end_to_end_test () {
     assert_key(&cb,4, &root_pkey.0,0);// root public key, 32 bytes
     assert_eq!(cb[36], 0x03);// subsequent chain length
     assert_key(&cb,37, &intr1_pkey.0,0);
-    assert_sig(&cb,69, &root_sig.0,0);
+    assert_sig(&cb,69, root_sig.as_ref(),0);
     assert_key(&cb,133, &intr2_pkey.0,0);
-    assert_sig(&cb,165, &intr1_sig.0,0);
+    assert_sig(&cb,165, intr1_sig.as_ref(),0);
     assert_key(&cb,229, &end_pkey.0,0);
-    assert_sig(&cb,261, &intr2_sig.0,0);
+    assert_sig(&cb,261, intr2_sig.as_ref(),0);
 
     let parsed_chain_res = trust_chain(&cb, root_key_store);
 
@@ -318,13 +323,13 @@ fn five_link_chain_end_to_end_test () {
     assert_key(&cb,4, &root_pkey.0,0);// root public key, 32 bytes
     assert_eq!(cb[36], 0x04);// subsequent chain length
     assert_key(&cb,37, &intr1_pkey.0,0);
-    assert_sig(&cb,69, &root_sig.0,0);
+    assert_sig(&cb,69, root_sig.as_ref(),0);
     assert_key(&cb,133, &intr2_pkey.0,0);
-    assert_sig(&cb,165, &intr1_sig.0,0);
+    assert_sig(&cb,165, intr1_sig.as_ref(),0);
     assert_key(&cb,229, &intr3_pkey.0,0);
-    assert_sig(&cb,261, &intr2_sig.0,0);
+    assert_sig(&cb,261, intr2_sig.as_ref(),0);
     assert_key(&cb,325, &end_pkey.0,0);
-    assert_sig(&cb,357, &intr3_sig.0,0);
+    assert_sig(&cb,357, intr3_sig.as_ref(),0);
 
     let parsed_chain_res = trust_chain(&cb, root_key_store);
 
@@ -349,7 +354,7 @@ fn pkey_should_parse () {
 fn sig_should_parse () {
     let (pkey, skey) = ed25519::gen_keypair();
     let sig = ed25519::sign_detached(&pkey.0, &skey);
-    let psig = signature(&sig.0);
+    let psig = signature(sig.as_ref());
     assert!(psig.is_ok());
     assert_eq!(psig.unwrap().1,sig);
 }
@@ -67,7 +67,7 @@ impl fmt::Display for TrustChain {
 }
 
 impl TrustChain {
-    pub fn root_only_chain(root_key: PublicKey, root_key_store:Box<RootKeysStore>) -> Result<TrustChain,TrustError>  {
+    pub fn root_only_chain(root_key: PublicKey, root_key_store:Box<dyn RootKeysStore>) -> Result<TrustChain,TrustError>  {
         if !root_key_store.contains_root_key(&root_key.0) {
             return Err(TrustError::NoRootKeyTrust)
         }
@@ -78,7 +78,7 @@ impl TrustChain {
         root_key: PublicKey,
         end_key: PublicKey,
         root_sig_over_end_key: Signature,
-        root_key_store:Box<RootKeysStore>) -> Result<TrustChain,TrustError> {
+        root_key_store:Box<dyn RootKeysStore>) -> Result<TrustChain,TrustError> {
         TrustChain::root_only_chain(root_key, root_key_store)
             .and_then(|chain| chain.append(end_key, root_sig_over_end_key))
     }
@@ -89,7 +89,7 @@ impl TrustChain {
         end_key: PublicKey,
         root_sig_over_intermediate_key: Signature,
         intermediate_sig_over_end_key: Signature,
-        root_key_store:Box<RootKeysStore>) -> Result<TrustChain,TrustError> {
+        root_key_store:Box<dyn RootKeysStore>) -> Result<TrustChain,TrustError> {
         TrustChain::root_only_chain(root_key, root_key_store)
             .and_then(|chain| chain.append(intermediate_key, root_sig_over_intermediate_key))
             .and_then(|chain| chain.append(end_key, intermediate_sig_over_end_key))
@@ -103,7 +103,7 @@ impl TrustChain {
         root_sig_over_intermediate1_key: Signature,
         intermediate1_sig_over_intermediate2_key: Signature,
         intermediate2_sig_over_end_key: Signature,
-        root_key_store:Box<RootKeysStore>) -> Result<TrustChain,TrustError> {
+        root_key_store:Box<dyn RootKeysStore>) -> Result<TrustChain,TrustError> {
         TrustChain::root_only_chain(root_key, root_key_store)
             .and_then(|chain| chain.append(intermediate1_key, root_sig_over_intermediate1_key))
             .and_then(|chain| chain.append(intermediate2_key, intermediate1_sig_over_intermediate2_key))
@@ -120,7 +120,7 @@ impl TrustChain {
         intermediate1_sig_over_intermediate2_key: Signature,
         intermediate2_sig_over_intermediate3_key: Signature,
         intermediate3_sig_over_end_key: Signature,
-        root_key_store:Box<RootKeysStore>) -> Result<TrustChain,TrustError> {
+        root_key_store:Box<dyn RootKeysStore>) -> Result<TrustChain,TrustError> {
         TrustChain::root_only_chain(root_key, root_key_store)
             .and_then(|chain| chain.append(intermediate1_key, root_sig_over_intermediate1_key))
             .and_then(|chain| chain.append(intermediate2_key, intermediate1_sig_over_intermediate2_key))
@@ -250,6 +250,58 @@ impl TrustChain {
         }
     }
 
+    pub fn links(&self) -> Vec<(PublicKey,Signature)> {
+        match self {
+            TrustChain::RootOnlyChain{..} => vec!(),
+            TrustChain::TwoLinkChain{
+                end_key,
+                root_sig_over_end_key,
+                ..
+            } => vec!(
+                (*end_key, *root_sig_over_end_key),
+            ),
+            TrustChain::ThreeLinkChain{
+                intermediate_key,
+                end_key,
+                root_sig_over_intermediate_key,
+                intermediate_sig_over_end_key,
+                ..
+            } => vec!(
+                (*intermediate_key, *root_sig_over_intermediate_key),
+                (*end_key, *intermediate_sig_over_end_key),
+            ),
+            TrustChain::FourLinkChain{
+                intermediate1_key,
+                intermediate2_key,
+                end_key,
+                root_sig_over_intermediate1_key,
+                intermediate1_sig_over_intermediate2_key,
+                intermediate2_sig_over_end_key,
+                ..
+            } => vec!(
+                (*intermediate1_key, *root_sig_over_intermediate1_key),
+                (*intermediate2_key, *intermediate1_sig_over_intermediate2_key),
+                (*end_key, *intermediate2_sig_over_end_key),
+            ),
+            TrustChain::FiveLinkChain{
+                intermediate1_key,
+                intermediate2_key,
+                intermediate3_key,
+                end_key,
+                root_sig_over_intermediate1_key,
+                intermediate1_sig_over_intermediate2_key,
+                intermediate2_sig_over_intermediate3_key,
+                intermediate3_sig_over_end_key,
+                ..
+            } => vec!(
+                (*intermediate1_key, *root_sig_over_intermediate1_key),
+                (*intermediate2_key, *intermediate1_sig_over_intermediate2_key),
+                (*intermediate3_key, *intermediate2_sig_over_intermediate3_key),
+                (*end_key, *intermediate3_sig_over_end_key),
+            ),
+        }
+    }
+
     pub fn verify_data (&self, untrusted_signature: &Signature, untrusted_data: &[u8]) -> Result<(),TrustError> {
         if !ed25519::verify_detached(untrusted_signature, untrusted_data, &self.end_key()) {
             return Err(TrustError::InvalidSignature)
@@ -279,7 +331,7 @@ impl TrustChain {
                 v.append(&mut root_key.0.clone().to_vec()); // 32 bytes
                 v.append(&mut vec!(0x01)); // 1 byte
                 v.append(&mut end_key.0.clone().to_vec());  // 32 bytes
-                v.append(&mut root_sig_over_end_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut root_sig_over_end_key.to_bytes().to_vec());  // 64 bytes
                 v
             },
             TrustChain::ThreeLinkChain {
@@ -294,9 +346,9 @@ impl TrustChain {
                 v.append(&mut root_key.0.clone().to_vec()); // 32 bytes
                 v.append(&mut vec!(0x02)); // 1 byte
                 v.append(&mut intermediate_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut root_sig_over_intermediate_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut root_sig_over_intermediate_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut end_key.0.clone().to_vec());  // 32 bytes
-                v.append(&mut intermediate_sig_over_end_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate_sig_over_end_key.to_bytes().to_vec());  // 64 bytes
                 v
             },
             TrustChain::FourLinkChain {
@@ -313,11 +365,11 @@ impl TrustChain {
                 v.append(&mut root_key.0.clone().to_vec()); // 32 bytes
                 v.append(&mut vec!(0x03)); // 1 byte chain length
                 v.append(&mut intermediate1_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut root_sig_over_intermediate1_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut root_sig_over_intermediate1_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut intermediate2_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut intermediate1_sig_over_intermediate2_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate1_sig_over_intermediate2_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut end_key.0.clone().to_vec());  // 32 bytes
-                v.append(&mut intermediate2_sig_over_end_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate2_sig_over_end_key.to_bytes().to_vec());  // 64 bytes
                 v
             },
             TrustChain::FiveLinkChain {
@@ -336,13 +388,13 @@ impl TrustChain {
                 v.append(&mut root_key.0.clone().to_vec()); // 32 bytes
                 v.append(&mut vec!(0x04)); // 1 byte chain length
                 v.append(&mut intermediate1_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut root_sig_over_intermediate1_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut root_sig_over_intermediate1_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut intermediate2_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut intermediate1_sig_over_intermediate2_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate1_sig_over_intermediate2_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut intermediate3_key.0.clone().to_vec()); // 32 bytes
-                v.append(&mut intermediate2_sig_over_intermediate3_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate2_sig_over_intermediate3_key.to_bytes().to_vec());  // 64 bytes
                 v.append(&mut end_key.0.clone().to_vec());  // 32 bytes
-                v.append(&mut intermediate3_sig_over_end_key.0.clone().to_vec());  // 64 bytes
+                v.append(&mut intermediate3_sig_over_end_key.to_bytes().to_vec());  // 64 bytes
                 v
             }
 
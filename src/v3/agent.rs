@@ -0,0 +1,260 @@
+use crate::v3::PUBLICKEYBYTES;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::signer::Signer;
+use ring::signature;
+use core::convert::TryInto;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+// message numbers from the ssh-agent protocol (draft-miller-ssh-agent)
+const SSH_AGENT_FAILURE            : u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER  : u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST      : u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE      : u8 = 14;
+
+const SSH_ED25519: &[u8] = b"ssh-ed25519";
+
+// refuse to buffer replies larger than this from a misbehaving agent.
+const MAX_AGENT_MESSAGE: usize = 256 * 1024;
+
+//--- A Signer whose Ed25519 secret stays inside an ssh-agent, reached over its Unix socket
+#[derive(Clone,Debug)]
+pub struct AgentSigner {
+    path: PathBuf,
+    key: PublicKey,
+}
+
+impl AgentSigner {
+    pub fn new <P: AsRef<Path>> (path: P, key: PublicKey) -> AgentSigner {
+        AgentSigner { path: path.as_ref().to_path_buf(), key }
+    }
+
+    pub fn from_env (key: PublicKey) -> Result<AgentSigner, TrustError> {
+        std::env::var_os("SSH_AUTH_SOCK")
+            .map(|path| AgentSigner::new(path, key))
+            .ok_or(TrustError::SigningFailed)
+    }
+
+    //--- the Ed25519 keys the agent holds, other key types are skipped
+    pub fn identities <P: AsRef<Path>> (path: P) -> Result<Vec<PublicKey>, TrustError> {
+        let reply = request(path.as_ref(), &[SSH_AGENTC_REQUEST_IDENTITIES])?;
+        let mut reader = Reader(&reply);
+
+        if reader.byte()? != SSH_AGENT_IDENTITIES_ANSWER { return Err(TrustError::SigningFailed) }
+
+        let mut keys = Vec::new();
+        for _ in 0..reader.u32()? {
+            let blob = reader.string()?;
+            let _comment = reader.string()?;
+            if let Ok(key) = parse_key_blob(blob) { keys.push(key) }
+        }
+        Ok(keys)
+    }
+}
+
+impl Signer for AgentSigner {
+    fn key (&self) -> PublicKey { self.key }
+
+    fn sign (&self, data: &[u8]) -> Result<Signature, TrustError> {
+        let mut msg = vec!(SSH_AGENTC_SIGN_REQUEST);
+        put_string(&mut msg, &key_blob(&self.key));
+        put_string(&mut msg, data);
+        msg.extend_from_slice(&0u32.to_be_bytes());// flags, none apply to ssh-ed25519
+
+        let reply = request(&self.path, &msg)?;
+        let mut reader = Reader(&reply);
+
+        match reader.byte()? {
+            SSH_AGENT_SIGN_RESPONSE => {
+                let mut sig_blob = Reader(reader.string()?);
+                if sig_blob.string()? != SSH_ED25519 { return Err(TrustError::SigningFailed) }
+                let sig = sig_blob.string()?;
+                // the agent may have signed with another identity, or not properly at all, so the
+                // signature is checked here rather than left to fail much later as InvalidSignature.
+                signature::UnparsedPublicKey::new(&signature::ED25519, self.key.as_ref())
+                    .verify(data, sig)
+                    .map_err(|_| TrustError::SigningFailed)?;
+                Signature::from_slice(sig).ok_or(TrustError::SigningFailed)
+            },
+            SSH_AGENT_FAILURE => Err(TrustError::SigningFailed),
+            _ => Err(TrustError::SigningFailed),
+        }
+    }
+}
+
+fn key_blob (key: &PublicKey) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(4 + SSH_ED25519.len() + 4 + PUBLICKEYBYTES);
+    put_string(&mut blob, SSH_ED25519);
+    put_string(&mut blob, key.as_ref());
+    blob
+}
+
+fn parse_key_blob (blob: &[u8]) -> Result<PublicKey, TrustError> {
+    let mut reader = Reader(blob);
+    if reader.string()? != SSH_ED25519 { return Err(TrustError::SigningFailed) }
+    PublicKey::from_slice(reader.string()?).ok_or(TrustError::SigningFailed)
+}
+
+fn put_string (out: &mut Vec<u8>, bytes: &[u8]) {
+    // safe because every string this module writes is far below 4GiB.
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn request (path: &Path, msg: &[u8]) -> Result<Vec<u8>, TrustError> {
+    let mut stream = UnixStream::connect(path).map_err(|_| TrustError::SigningFailed)?;
+    let mut framed = Vec::with_capacity(4 + msg.len());
+    put_string(&mut framed, msg);
+    stream.write_all(&framed).map_err(|_| TrustError::SigningFailed)?;
+    read_message(&mut stream)
+}
+
+fn read_message <R: Read> (input: &mut R) -> Result<Vec<u8>, TrustError> {
+    let mut len = [0u8;4];
+    input.read_exact(&mut len).map_err(|_| TrustError::SigningFailed)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len == 0 || len > MAX_AGENT_MESSAGE { return Err(TrustError::SigningFailed) }
+    let mut msg = vec![0u8;len];
+    input.read_exact(&mut msg).map_err(|_| TrustError::SigningFailed)?;
+    Ok(msg)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl <'a> Reader<'a> {
+    fn byte (&mut self) -> Result<u8, TrustError> {
+        let (b, rest) = self.0.split_first().ok_or(TrustError::SigningFailed)?;
+        self.0 = rest;
+        Ok(*b)
+    }
+
+    fn u32 (&mut self) -> Result<u32, TrustError> {
+        if self.0.len() < 4 { return Err(TrustError::SigningFailed) }
+        let (n, rest) = self.0.split_at(4);
+        self.0 = rest;
+        Ok(u32::from_be_bytes(n.try_into().map_err(|_| TrustError::SigningFailed)?))
+    }
+
+    fn string (&mut self) -> Result<&'a [u8], TrustError> {
+        let len = self.u32()? as usize;
+        if self.0.len() < len { return Err(TrustError::SigningFailed) }
+        let (s, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::Ed25519KeyPair;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+    use crate::v3::SIGNATUREBYTES;
+    use super::*;
+
+    fn signature_blob (sig: &Signature) -> Vec<u8> {
+        let mut blob = Vec::with_capacity(4 + SSH_ED25519.len() + 4 + SIGNATUREBYTES);
+        put_string(&mut blob, SSH_ED25519);
+        put_string(&mut blob, sig.as_ref());
+        blob
+    }
+
+    // A stand-in agent that holds a single ring key pair and answers `requests` messages.
+    fn spawn_agent (name: &str, keypair: Ed25519KeyPair, requests: usize) -> PathBuf {
+        spawn_agent_with(name, keypair, requests, false)
+    }
+
+    // As spawn_agent, but with `corrupt` the signatures it hands back are off by a bit.
+    fn spawn_agent_with (name: &str, keypair: Ed25519KeyPair, requests: usize, corrupt: bool) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("trustchain-agent-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let msg = read_message(&mut stream).unwrap();
+                let mut reader = Reader(&msg);
+                let mut reply = Vec::new();
+
+                match reader.byte().unwrap() {
+                    SSH_AGENTC_REQUEST_IDENTITIES => {
+                        reply.push(SSH_AGENT_IDENTITIES_ANSWER);
+                        reply.extend_from_slice(&2u32.to_be_bytes());
+                        let mut rsa_blob = Vec::new();
+                        put_string(&mut rsa_blob, b"ssh-rsa");
+                        put_string(&mut reply, &rsa_blob);
+                        put_string(&mut reply, b"some rsa key");
+                        put_string(&mut reply, &key_blob(&Signer::key(&keypair)));
+                        put_string(&mut reply, b"trust chain root");
+                    },
+                    SSH_AGENTC_SIGN_REQUEST => {
+                        let blob = reader.string().unwrap();
+                        let data = reader.string().unwrap();
+                        if blob == key_blob(&Signer::key(&keypair)).as_slice() {
+                            let mut sig = Signer::sign(&keypair, data).unwrap().as_ref().to_vec();
+                            if corrupt { sig[0] ^= 0x01 }
+                            reply.push(SSH_AGENT_SIGN_RESPONSE);
+                            put_string(&mut reply, &signature_blob(&Signature::from_slice(&sig).unwrap()));
+                        } else {
+                            reply.push(SSH_AGENT_FAILURE);
+                        }
+                    },
+                    _ => reply.push(SSH_AGENT_FAILURE),
+                }
+
+                let mut framed = Vec::new();
+                put_string(&mut framed, &reply);
+                stream.write_all(&framed).unwrap();
+            }
+        });
+        path
+    }
+
+    #[test]
+    fn agent_signer_should_sign_with_agent_held_key () {
+        let keypair  = Ed25519KeyPair::from_seed_unchecked(&[0x11;32]).unwrap();
+        let key      = Signer::key(&keypair);
+        let expected = Signer::sign(&keypair, b"test data").unwrap();
+        let path     = spawn_agent("sign", keypair, 2);
+
+        assert_eq!(AgentSigner::identities(&path), Ok(vec!(key)));
+
+        let signer   = AgentSigner::new(&path, key);
+
+        assert_eq!(signer.sign(b"test data"), Ok(expected));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn agent_signer_should_fail_for_unknown_key () {
+        let keypair  = Ed25519KeyPair::from_seed_unchecked(&[0x12;32]).unwrap();
+        let other    = Ed25519KeyPair::from_seed_unchecked(&[0x13;32]).unwrap();
+        let path     = spawn_agent("unknown", keypair, 1);
+        let signer   = AgentSigner::new(&path, Signer::key(&other));
+
+        assert_eq!(signer.sign(b"test data"), Err(TrustError::SigningFailed));
+
+        let _ = std::fs::remove_file(&path);
+        let missing  = AgentSigner::new(&path, Signer::key(&other));
+
+        assert_eq!(missing.sign(b"test data"), Err(TrustError::SigningFailed));
+    }
+
+    #[test]
+    fn agent_signer_should_refuse_signatures_that_do_not_verify () {
+        let keypair  = Ed25519KeyPair::from_seed_unchecked(&[0x14;32]).unwrap();
+        let key      = Signer::key(&keypair);
+        let path     = spawn_agent_with("corrupt", keypair, 1, true);
+        let signer   = AgentSigner::new(&path, key);
+
+        assert_eq!(signer.sign(b"test data"), Err(TrustError::SigningFailed));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::expiry::Expiry;
+use crate::v3::link::TrustLink;
+use std::sync::Arc;
+
+//--- A trusted root key together with the terms it is trusted on
+//
+// A chain can only start at an anchor that is not distrusted and, when it has a validity period, only
+// while that period lasts; max_depth bounds the number of links the chain may hold beneath the root.
+#[derive(Clone,Debug,PartialEq)]
+pub struct TrustAnchor {
+    key: PublicKey,
+    // shared rather than owned so handing an anchor out of a store never allocates.
+    name: Option<Arc<str>>,
+    validity: Option<Expiry>,
+    max_depth: Option<u8>,
+    distrusted: bool,
+}
+
+impl TrustAnchor {
+    pub fn new (key: PublicKey) -> TrustAnchor {
+        TrustAnchor { key, name: None, validity: None, max_depth: None, distrusted: false }
+    }
+
+    pub fn with_name (mut self, name: &str) -> TrustAnchor {
+        self.name = Some(Arc::from(name));
+        self
+    }
+
+    pub fn with_validity (mut self, validity: Expiry) -> TrustAnchor {
+        self.validity = Some(validity);
+        self
+    }
+
+    pub fn with_max_depth (mut self, max_depth: u8) -> TrustAnchor {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn with_distrusted (mut self, distrusted: bool) -> TrustAnchor {
+        self.distrusted = distrusted;
+        self
+    }
+
+    pub fn key (&self) -> PublicKey { self.key }
+
+    pub fn name (&self) -> Option<&str> { self.name.as_deref() }
+
+    pub fn validity (&self) -> Option<Expiry> { self.validity }
+
+    pub fn max_depth (&self) -> Option<u8> { self.max_depth }
+
+    pub fn is_distrusted (&self) -> bool { self.distrusted }
+
+    //--- whether a chain may start at this anchor at the given time
+    pub fn admits_at (&self, at: u64) -> Result<(),TrustError> {
+        if self.distrusted { return Err(TrustError::DistrustedAnchor) }
+        self.is_valid_at(at)
+    }
+
+    pub fn is_valid_at (&self, at: u64) -> Result<(),TrustError> {
+        match self.validity {
+            Some(validity) if !validity.is_valid_at(at) => Err(TrustError::InvalidExpiry),
+            _ => Ok(()),
+        }
+    }
+
+    //--- whether a chain may hold this many links beneath the root
+    pub fn allows_depth (&self, depth: usize) -> Result<(),TrustError> {
+        match self.max_depth {
+            Some(max) if depth > max as usize => Err(TrustError::PathLengthExceeded),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<PublicKey> for TrustAnchor {
+    fn from (key: PublicKey) -> TrustAnchor { TrustAnchor::new(key) }
+}
+
+// the anchor stands in for the root at the head of a verified chain.
+impl TrustLink for TrustAnchor {
+    fn key       (&self) -> PublicKey {self.key}
+    fn signature (&self) -> Option<Signature> {None}
+    fn expiry    (&self) -> Option<Expiry> {None}
+    fn is_root   (&self) -> bool {true}
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::PUBLICKEYBYTES;
+    use crate::v3::expiry::{NotBefore, NotAfter};
+    use super::*;
+
+    #[test]
+    fn anchor_should_enforce_its_terms () {
+        let key    = PublicKey::from_slice(&[0x19;PUBLICKEYBYTES]).unwrap();
+        let plain  = TrustAnchor::from(key);
+        let bound  = TrustAnchor::new(key)
+            .with_name("prod root")
+            .with_validity(Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap())
+            .with_max_depth(1);
+
+        assert_eq!(plain.admits_at(0), Ok(()));
+        assert_eq!(plain.allows_depth(usize::MAX), Ok(()));
+        assert_eq!(bound.name(), Some("prod root"));
+        assert_eq!(bound.admits_at(1_500), Ok(()));
+        assert_eq!(bound.admits_at(999), Err(TrustError::InvalidExpiry));
+        assert_eq!(bound.admits_at(2_001), Err(TrustError::InvalidExpiry));
+        assert_eq!(bound.allows_depth(1), Ok(()));
+        assert_eq!(bound.allows_depth(2), Err(TrustError::PathLengthExceeded));
+        assert_eq!(bound.with_distrusted(true).admits_at(1_500), Err(TrustError::DistrustedAnchor));
+    }
+}
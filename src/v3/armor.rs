@@ -0,0 +1,253 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::fingerprint::Fingerprint;
+use crate::v3::signature::Signature;
+use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::trust_chain::TrustChain;
+use crate::v3::parsers::trust_chain_strict;
+use core::fmt;
+
+pub const TRUST_CHAIN_LABEL : &str = "TRUST CHAIN";
+pub const PUBLIC_KEY_LABEL  : &str = "TRUST CHAIN PUBLIC KEY";
+pub const SIGNATURE_LABEL   : &str = "TRUST CHAIN SIGNATURE";
+
+const LINE_WIDTH         : usize = 64;
+const FINGERPRINT_HEADER : &str  = "Fingerprint";
+
+//--- A PEM-style text block: a labelled base64 body preceded by optional `Name: value` headers
+//
+//     -----BEGIN TRUST CHAIN-----
+//     Comment: build signing chain
+//
+//     VEMAAwM...
+//     -----END TRUST CHAIN-----
+#[derive(Clone,Debug,PartialEq)]
+pub struct Armor {
+    label: String,
+    headers: Vec<(String,String)>,
+    body: Vec<u8>,
+}
+
+impl Armor {
+    pub fn new (label: &str, body: Vec<u8>) -> Armor {
+        Armor { label: label.to_string(), headers: Vec::new(), body }
+    }
+
+    //--- fails with InvalidArmor when the name or value would not survive being written out and read back
+    //
+    // An empty value is refused too, `Name: ` loses its trailing space and would read back as body text.
+    pub fn header (mut self, name: &str, value: &str) -> Result<Armor, TrustError> {
+        if !is_header_name(name) || value.is_empty() || value.trim() != value || value.chars().any(char::is_control) {
+            return Err(TrustError::InvalidArmor)
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+        Ok(self)
+    }
+
+    pub fn label (&self) -> &str { &self.label }
+
+    pub fn headers (&self) -> &[(String,String)] { &self.headers }
+
+    //--- first value of the named header, names compare case-insensitively
+    pub fn get_header (&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    pub fn body (&self) -> &[u8] { &self.body }
+
+    //--- Reads exactly one armored block, only surrounding whitespace is allowed around it
+    pub fn parse (text: &str) -> Result<Armor, TrustError> {
+        let mut lines = text.trim().lines().map(str::trim_end);
+
+        let label = lines.next()
+            .and_then(|l| l.strip_prefix("-----BEGIN "))
+            .and_then(|l| l.strip_suffix("-----"))
+            .filter(|l| !l.is_empty())
+            .ok_or(TrustError::InvalidArmor)?;
+        let end   = format!("-----END {}-----", label);
+
+        let mut armor   = Armor::new(label, Vec::new());
+        let mut encoded = String::new();
+        let mut in_body = false;
+        let mut closed  = false;
+
+        for line in lines.by_ref() {
+            if line == end { closed = true; break }
+            match (in_body, line.split_once(": ")) {
+                (false, _) if line.is_empty() => in_body = true,
+                (true, _) if line.is_empty()  => return Err(TrustError::InvalidArmor),
+                // base64 never contains ':', so a header-less block can start its body right away.
+                (false, Some((name, value))) => armor = armor.header(name, value)?,
+                _ => { in_body = true; encoded.push_str(line.trim_start()) },
+            }
+        }
+
+        if !closed || lines.next().is_some() { return Err(TrustError::InvalidArmor) }
+        armor.body = STANDARD.decode(encoded).map_err(|_| TrustError::InvalidArmor)?;
+        Ok(armor)
+    }
+
+    fn expect (self, label: &str) -> Result<Armor, TrustError> {
+        if self.label != label { return Err(TrustError::InvalidArmor) }
+        Ok(self)
+    }
+}
+
+fn is_header_name (name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+impl fmt::Display for Armor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "-----BEGIN {}-----", self.label)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value)?;
+        }
+        if !self.headers.is_empty() { writeln!(f)? }
+        let encoded = STANDARD.encode(&self.body);
+        // safe because base64 output is ascii, so every chunk is valid utf8.
+        for line in encoded.as_bytes().chunks(LINE_WIDTH) {
+            writeln!(f, "{}", core::str::from_utf8(line).unwrap())?;
+        }
+        writeln!(f, "-----END {}-----", self.label)
+    }
+}
+
+impl TrustChain {
+    pub fn to_armor (&self) -> Armor { Armor::new(TRUST_CHAIN_LABEL, self.as_bytes()) }
+}
+
+impl PublicKey {
+    //--- carries a Fingerprint header, which armored_public_key checks against the body
+    pub fn to_armor (&self) -> Armor {
+        let armor = Armor::new(PUBLIC_KEY_LABEL, self.as_ref().to_vec());
+        // safe because a fingerprint's Display is a single line of ascii.
+        armor.header(FINGERPRINT_HEADER, &self.fingerprint().to_string()).unwrap()
+    }
+}
+
+impl Signature {
+    pub fn to_armor (&self) -> Armor { Armor::new(SIGNATURE_LABEL, self.as_ref().to_vec()) }
+}
+
+//--- Armored counterpart of parsers::trust_chain_strict, the body must be exactly one verified chain
+pub fn armored_trust_chain <'ks> (text: &str, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain,TrustError> {
+    let armor = Armor::parse(text)?.expect(TRUST_CHAIN_LABEL)?;
+    trust_chain_strict(&mut armor.body(), root_keys_store, clock)
+}
+
+pub fn armored_public_key (text: &str) -> Result<PublicKey,TrustError> {
+    let armor = Armor::parse(text)?.expect(PUBLIC_KEY_LABEL)?;
+    let key   = PublicKey::from_slice(armor.body()).ok_or(TrustError::InvalidArmor)?;
+    match armor.get_header(FINGERPRINT_HEADER).map(str::parse::<Fingerprint>) {
+        Some(Ok(fp)) if fp != key.fingerprint() => Err(TrustError::InvalidArmor),
+        Some(Err(_))                            => Err(TrustError::InvalidArmor),
+        _                                       => Ok(key),
+    }
+}
+
+pub fn armored_signature (text: &str) -> Result<Signature,TrustError> {
+    let armor = Armor::parse(text)?.expect(SIGNATURE_LABEL)?;
+    Signature::from_slice(armor.body()).ok_or(TrustError::InvalidArmor)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::builder::ChainBuilder;
+    use crate::v3::clock::FixedClock;
+    use crate::v3::expiry::{Expiry, NotBefore, NotAfter};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn fixture () -> (Vec<PublicKey>, Ed25519KeyPair, TrustChain) {
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x15;32]).unwrap();
+        let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let (chain, end)   = ChainBuilder::new(&root_keypair)
+            .intermediate(Some(expiry))
+            .build(None, &root_key_store, &FixedClock::new(1_500))
+            .unwrap();
+        (root_key_store, end, chain)
+    }
+
+    #[test]
+    fn chain_should_round_trip_through_armor () {
+        let (root_key_store, _, chain) = fixture();
+        let clock          = FixedClock::new(1_500);
+        let text           = chain.to_armor()
+            .header("Comment", "build signing chain").unwrap()
+            .header("Fingerprint", "SHA256:abc").unwrap()
+            .to_string();
+
+        assert!(text.starts_with("-----BEGIN TRUST CHAIN-----\nComment: build signing chain\nFingerprint: SHA256:abc\n\n"));
+        assert!(text.ends_with("\n-----END TRUST CHAIN-----\n"));
+        assert!(text.lines().all(|l| l.len() <= LINE_WIDTH));
+        assert_eq!(armored_trust_chain(&text, &root_key_store, &clock).as_ref(), Ok(&chain));
+
+        let armor          = Armor::parse(&text.replace('\n', "\r\n")).unwrap();
+
+        assert_eq!(armor.get_header("comment"), Some("build signing chain"));
+        assert_eq!(armor.body(), chain.as_bytes().as_slice());
+
+        let bare           = chain.to_armor().to_string();
+
+        assert_eq!(armored_trust_chain(&format!("\n  {}\n", bare), &root_key_store, &clock), Ok(chain));
+        assert_eq!(armored_trust_chain(&bare, &Vec::<PublicKey>::new(), &clock), Err(TrustError::NoRootKeyTrust));
+        assert_eq!(armored_trust_chain(&bare, &root_key_store, &FixedClock::new(2_001)), Err(TrustError::InvalidExpiry));
+    }
+
+    #[test]
+    fn keys_and_signatures_should_round_trip_through_armor () {
+        let (_, end, chain) = fixture();
+        let key            = chain.last().key();
+        let sig            = Signature::from(end.sign(b"test data"));
+
+        let armored        = key.to_armor().header("Comment", "end key").unwrap().to_string();
+
+        assert!(armored.contains(&format!("\nFingerprint: {}\n", key.fingerprint())));
+        assert_eq!(armored_public_key(&armored), Ok(key));
+        assert_eq!(armored_public_key(&Armor::new(PUBLIC_KEY_LABEL, key.as_ref().to_vec()).to_string()), Ok(key));
+
+        let other          = PublicKey::from_slice(&[0x01;32]).unwrap();
+        let mislabelled    = Armor::new(PUBLIC_KEY_LABEL, key.as_ref().to_vec()).header("Fingerprint", &other.fingerprint().to_string()).unwrap();
+
+        assert_eq!(armored_public_key(&mislabelled.to_string()), Err(TrustError::InvalidArmor));
+        assert_eq!(armored_public_key(&armored.replace("Fingerprint: tc3", "Fingerprint: xx3")), Err(TrustError::InvalidArmor));
+        assert_eq!(armored_signature(&sig.to_armor().to_string()), Ok(sig));
+        assert_eq!(armored_public_key(&sig.to_armor().to_string()), Err(TrustError::InvalidArmor));
+        assert_eq!(armored_public_key(&Armor::new(PUBLIC_KEY_LABEL, vec!(0u8;31)).to_string()), Err(TrustError::InvalidArmor));
+    }
+
+    #[test]
+    fn malformed_armor_should_be_rejected () {
+        let (root_key_store, _, chain) = fixture();
+        let clock          = FixedClock::new(1_500);
+        let text           = chain.to_armor().to_string();
+        let mut trailing   = chain.as_bytes();
+        trailing.push(0);
+
+        let cases = vec!(
+            text.replace("-----END TRUST CHAIN-----", "-----END PUBLIC KEY-----"),
+            text.replace("-----END TRUST CHAIN-----\n", ""),
+            text.replace("-----BEGIN ", "-----BEGIN"),
+            format!("{}junk\n", text),
+            format!("junk\n{}", text),
+            text.replacen('\n', "\nComment\x07: bell\n\n", 1),
+            text.replacen("\n", "\n\n", 2),
+            text.replacen('\n', "\n!!!!\n", 1),
+        );
+
+        for case in cases {
+            assert_eq!(armored_trust_chain(&case, &root_key_store, &clock), Err(TrustError::InvalidArmor), "{}", case);
+        }
+
+        assert_eq!(armored_trust_chain(&Armor::new(TRUST_CHAIN_LABEL, trailing).to_string(), &root_key_store, &clock), Err(TrustError::TrailingData));
+        assert_eq!(Armor::new(TRUST_CHAIN_LABEL, vec!()).header("Bad: name", "v"), Err(TrustError::InvalidArmor));
+        assert_eq!(Armor::new(TRUST_CHAIN_LABEL, vec!()).header("Comment", "two\nlines"), Err(TrustError::InvalidArmor));
+        assert_eq!(Armor::new(TRUST_CHAIN_LABEL, vec!()).header("Comment", ""), Err(TrustError::InvalidArmor));
+        assert_eq!(armored_trust_chain(&text.replacen('\n', "\nComment: \n\n", 1), &root_key_store, &clock), Err(TrustError::InvalidArmor));
+    }
+}
@@ -0,0 +1,257 @@
+use crate::v3::trust_chain::{TrustChain, MAX_ENCODED_LEN};
+use crate::v3::link::{Root, Link};
+use crate::v3::PUBLICKEYBYTES;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::decoder::{LinkLayout, Decoded, MAX_LINK_LEN, link_layout, decode_link};
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// The store and clock are taken as generics rather than trait objects so that, when both are Sync,
+// the returned future is Send and can be driven from a spawned tokio task.
+
+async fn read_exact_async <R> (input: &mut R, buf: &mut [u8]) -> Result<(), TrustError>
+where R: AsyncRead + Unpin {
+    input.read_exact(buf).await.map(|_| ()).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => TrustError::UnexpectedEof,
+        kind => TrustError::ReadFailed(kind),
+    })
+}
+
+// the stream counterpart of decoder::read_link_with, reading up to whatever decode_link asks for.
+async fn read_link_async <R> (input: &mut R, layout: LinkLayout) -> Result<Link, TrustError>
+where R: AsyncRead + Unpin {
+    let mut buf  = [0u8;MAX_LINK_LEN];
+    let mut have = 0;
+    loop {
+        match decode_link(&buf[..have], layout)? {
+            Decoded::Link(link) => return Ok(link.to_link()),
+            Decoded::Needs(len) => {
+                read_exact_async(input, &mut buf[have..len]).await?;
+                have = len;
+            },
+        }
+    }
+}
+
+//--- Async counterpart of parsers::trust_chain, reading exactly one encoded chain off the stream
+pub async fn trust_chain_async <'ks, R, S, C> (input: &mut R, root_keys_store: &'ks S, clock: &C) -> Result<TrustChain,TrustError>
+where R: AsyncRead + Unpin,
+      S: RootKeysStore<'ks> + 'ks,
+      C: Clock {
+    trust_chain_async_bounded(input, root_keys_store, clock).await
+}
+
+//--- As trust_chain_async, for verifiers that take chains of up to N links rather than MAXCHAINLINKS
+pub async fn trust_chain_async_bounded <'ks, R, S, C, const N: usize> (input: &mut R, root_keys_store: &'ks S, clock: &C) -> Result<TrustChain<N>,TrustError>
+where R: AsyncRead + Unpin,
+      S: RootKeysStore<'ks> + 'ks,
+      C: Clock {
+    let mut buf = [0u8;5];
+    read_exact_async(input, &mut buf).await?;
+
+    let layout = link_layout(&buf[0..4])?;
+
+    let chain_length = buf[4] ;
+    if chain_length as usize > N {
+        return Err(TrustError::MaxChainLengthExceeded)
+    }
+
+    if chain_length < 1 {
+        return Err(TrustError::InvalidTrustChain)
+    }
+
+    let mut buf = [0u8;PUBLICKEYBYTES];
+    read_exact_async(input, &mut buf).await?;
+    let root = PublicKey::from_slice(&buf).map(Root::new).ok_or(TrustError::InvalidTrustChain)?;
+
+    let mut chain = TrustChain::bounded(root_keys_store, root, clock)?;
+    for _ in 1..chain_length {
+        chain = chain.append(read_link_async(input, layout).await?, clock)?;
+    }
+    Ok(chain)
+}
+
+impl <const N: usize> TrustChain<N> {
+    //--- Async counterpart of write_to, again a single write_all of the whole encoding
+    pub async fn write_to_async <W> (&self, out: &mut W) -> io::Result<()>
+    where W: AsyncWrite + Unpin {
+        let mut buf = [0u8;MAX_ENCODED_LEN];
+        out.write_all(&self.encode_with(&mut buf)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::builder::ChainBuilder;
+    use crate::v3::clock::FixedClock;
+    use crate::v3::expiry::{Expiry, NotBefore, NotAfter};
+    use crate::v3::parsers::{trust_chain, trust_chain_bounded};
+    use crate::v3::MAXCHAINLINKS;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn fixture () -> (Vec<PublicKey>, TrustChain) {
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x21;32]).unwrap();
+        let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let (chain, _)     = ChainBuilder::new(&root_keypair)
+            .intermediate(Some(expiry))
+            .intermediate(None)
+            .build(Some(expiry), &root_key_store, &FixedClock::new(1_500))
+            .unwrap();
+        (root_key_store, chain)
+    }
+
+    #[tokio::test]
+    async fn chain_should_parse_from_duplex_stream () {
+        let (root_key_store, chain) = fixture();
+        let cb             = chain.as_bytes();
+        let (mut tx, mut rx) = tokio::io::duplex(16);
+
+        // dribble the chain across many small writes, followed by the next frame on the stream.
+        let writer         = tokio::spawn(async move {
+            for chunk in cb.chunks(7) { tx.write_all(chunk).await.unwrap() }
+            tx.write_all(b"next frame").await.unwrap();
+        });
+
+        let parsed         = trust_chain_async(&mut rx, &root_key_store, &FixedClock::new(1_500)).await;
+
+        assert_eq!(parsed, Ok(chain));
+
+        let mut next       = [0u8;10];
+        rx.read_exact(&mut next).await.unwrap();
+
+        assert_eq!(&next, b"next frame");
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn chain_parse_future_should_be_send () {
+        let (root_key_store, chain) = fixture();
+        let (mut tx, mut rx) = tokio::io::duplex(1024);
+        tx.write_all(&chain.as_bytes()).await.unwrap();
+
+        let parsed         = tokio::spawn(async move {
+            trust_chain_async(&mut rx, &root_key_store, &FixedClock::new(1_500)).await
+        });
+
+        assert_eq!(parsed.await.unwrap(), Ok(chain));
+    }
+
+    #[tokio::test]
+    async fn async_errors_should_match_blocking_parser () {
+        let (root_key_store, chain) = fixture();
+        let cb             = chain.as_bytes();
+        let mut bad_sig    = cb.clone();
+        bad_sig[80]       ^= 0x01;
+        let mut too_long   = cb.clone();
+        too_long[4]        = MAXCHAINLINKS + 1;
+
+        let cases: Vec<(Vec<u8>, u64)> = vec!(
+            (cb.clone(), 1_500),
+            (cb.clone(), 2_001),
+            (cb[..cb.len() - 1].to_vec(), 1_500),
+            (cb[..3].to_vec(), 1_500),
+            (bad_sig, 1_500),
+            (too_long, 1_500),
+            (b"TC\x00\x03\x00".to_vec(), 1_500),
+        );
+
+        for (input, at) in cases {
+            let clock      = FixedClock::new(at);
+            let (mut tx, mut rx) = tokio::io::duplex(1024);
+            tx.write_all(&input).await.unwrap();
+            drop(tx);
+
+            let parsed     = trust_chain_async(&mut rx, &root_key_store, &clock).await;
+
+            assert_eq!(parsed, trust_chain(&mut input.as_slice(), &root_key_store, &clock));
+        }
+
+        let (mut tx, mut rx) = tokio::io::duplex(1024);
+        tx.write_all(&cb).await.unwrap();
+
+        assert_eq!(trust_chain_async(&mut rx, &Vec::<PublicKey>::new(), &FixedClock::new(1_500)).await, Err(TrustError::NoRootKeyTrust));
+    }
+
+    #[tokio::test]
+    async fn attributed_links_should_parse_like_the_blocking_parser () {
+        use crate::v3::attributes::{Attributes, KeyUsage};
+        use crate::v3::builder::issue_link_with_attributes;
+        use crate::v3::link::Root;
+
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x22;32]).unwrap();
+        let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+        let end            = PublicKey::from_slice(&[0x07;32]).unwrap();
+        let clock          = FixedClock::new(1_500);
+        let attributes     = Attributes::new().with_key_usage(KeyUsage::SIGN_DATA).unwrap().with_label("end").unwrap();
+        let chain          = TrustChain::new(&root_key_store, Root::new(root_key_store[0]), &clock).unwrap()
+            .append(issue_link_with_attributes(&root_keypair, end, None, attributes).unwrap(), &clock).unwrap();
+        let cb             = chain.as_bytes();
+        let mut relabelled = cb.clone();
+        *relabelled.last_mut().unwrap() ^= 0x01;
+
+        for input in [cb, relabelled] {
+            let (mut tx, mut rx) = tokio::io::duplex(1024);
+            tx.write_all(&input).await.unwrap();
+            drop(tx);
+
+            let parsed     = trust_chain_async(&mut rx, &root_key_store, &clock).await;
+
+            assert_eq!(parsed, trust_chain(&mut input.as_slice(), &root_key_store, &clock));
+        }
+        assert_eq!(trust_chain(&mut chain.as_bytes().as_slice(), &root_key_store, &clock).map(|c| c.last().attributes()), Ok(attributes));
+    }
+
+    #[tokio::test]
+    async fn deeper_chains_should_read_back_when_bounded () {
+        use crate::v3::builder::issue_link;
+
+        let keypairs       = (1..=7).map(|seed| Ed25519KeyPair::from_seed_unchecked(&[seed;32]).unwrap()).collect::<Vec<_>>();
+        let keys           = keypairs.iter().map(|k| PublicKey::from_slice(k.public_key().as_ref()).unwrap()).collect::<Vec<_>>();
+        let root_key_store = vec!(keys[0]);
+        let clock          = FixedClock::new(1_500);
+        let mut chain      = TrustChain::<7>::bounded(&root_key_store, Root::new(keys[0]), &clock).unwrap();
+        for at in 1..7 {
+            chain          = chain.append(issue_link(&keypairs[at - 1], keys[at], None).unwrap(), &clock).unwrap();
+        }
+        let (mut tx, mut rx) = tokio::io::duplex(64);
+
+        let writer         = tokio::spawn(async move {
+            chain.write_to_async(&mut tx).await.unwrap();
+            chain
+        });
+
+        let parsed         = trust_chain_async_bounded::<_, _, _, 7>(&mut rx, &root_key_store, &clock).await;
+        let chain          = writer.await.unwrap();
+
+        assert_eq!(parsed, Ok(chain.clone()));
+        assert_eq!(parsed, trust_chain_bounded(&mut chain.as_bytes().as_slice(), &root_key_store, &clock));
+        assert_eq!(trust_chain_async(&mut chain.as_bytes().as_slice(), &root_key_store, &clock).await, Err(TrustError::MaxChainLengthExceeded));
+    }
+
+    #[tokio::test]
+    async fn chains_written_async_should_read_back_in_order () {
+        let (root_key_store, chain) = fixture();
+        let root_only      = TrustChain::new(&root_key_store, Root::new(root_key_store[0]), &FixedClock::new(1_500)).unwrap();
+        let (mut tx, mut rx) = tokio::io::duplex(64);
+
+        let writer         = tokio::spawn(async move {
+            chain.write_to_async(&mut tx).await.unwrap();
+            root_only.write_to_async(&mut tx).await.unwrap();
+            (chain, root_only)
+        });
+
+        let clock          = FixedClock::new(1_500);
+        let first          = trust_chain_async(&mut rx, &root_key_store, &clock).await;
+        let second         = trust_chain_async(&mut rx, &root_key_store, &clock).await;
+        let (chain, root_only) = writer.await.unwrap();
+
+        assert_eq!(first, Ok(chain));
+        assert_eq!(second, Ok(root_only));
+    }
+}
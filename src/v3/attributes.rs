@@ -0,0 +1,274 @@
+use crate::v3::MAXATTRIBUTEBYTES;
+use crate::v3::error::TrustError;
+use core::fmt;
+
+// the type byte of an attribute: the critical bit over a 7 bit id.
+pub const ATTRIBUTE_CRITICAL  : u8 = 0x80;
+pub const ATTRIBUTE_KEY_USAGE : u8 = 0x01;
+pub const ATTRIBUTE_LABEL     : u8 = 0x02;
+pub const ATTRIBUTE_MAX_DEPTH : u8 = 0x03;
+
+// ids left to applications; every other id not named above is reserved for later versions of the format.
+pub const ATTRIBUTE_EXTENSIONS : core::ops::RangeInclusive<u8> = 0x40..=0x7f;
+
+//--- What a link's key may sign
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct KeyUsage(u8);
+
+impl KeyUsage {
+    pub const SIGN_LINKS : KeyUsage = KeyUsage(0x01);
+    pub const SIGN_DATA  : KeyUsage = KeyUsage(0x02);
+    pub const BOTH       : KeyUsage = KeyUsage(0x03);
+
+    //--- None for no usage at all or for bits this version does not define
+    pub fn from_bits (bits: u8) -> Option<KeyUsage> {
+        if bits == 0 || bits & !KeyUsage::BOTH.0 != 0 { return None }
+        Some(KeyUsage(bits))
+    }
+
+    pub fn bits (&self) -> u8 { self.0 }
+
+    pub fn can_sign_links (&self) -> bool { self.0 & KeyUsage::SIGN_LINKS.0 != 0 }
+
+    pub fn can_sign_data (&self) -> bool { self.0 & KeyUsage::SIGN_DATA.0 != 0 }
+}
+
+impl fmt::Display for KeyUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.can_sign_links(), self.can_sign_data()) {
+            (true, true) => write!(f, "sign-links,sign-data"),
+            (true, _)    => write!(f, "sign-links"),
+            _            => write!(f, "sign-data"),
+        }
+    }
+}
+
+//--- One entry of an attribute block, borrowed from it
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Attribute<'a> {
+    id: u8,
+    critical: bool,
+    value: &'a [u8],
+}
+
+impl <'a> Attribute<'a> {
+    pub fn id (&self) -> u8 { self.id }
+
+    pub fn is_critical (&self) -> bool { self.critical }
+
+    pub fn value (&self) -> &'a [u8] { self.value }
+
+    //--- whether this version of the format gives the attribute a meaning of its own
+    pub fn is_standard (&self) -> bool { matches!(self.id, ATTRIBUTE_KEY_USAGE | ATTRIBUTE_LABEL | ATTRIBUTE_MAX_DEPTH) }
+}
+
+//--- The signed attributes of a link, kept as their TLV encoding
+//
+// Each attribute is a type byte, a length byte and that many bytes of value, in strictly ascending
+// order of id so that every set of attributes has exactly one encoding. The block lives inline so a
+// Link stays Copy; only blocks that pass from_slice's checks are ever held.
+#[derive(Copy,Clone)]
+pub struct Attributes {
+    len: usize,
+    bytes: [u8;MAXATTRIBUTEBYTES],
+}
+
+impl Attributes {
+    pub fn new () -> Attributes {
+        Attributes { len: 0, bytes: [0u8;MAXATTRIBUTEBYTES] }
+    }
+
+    pub fn from_slice (b: &[u8]) -> Result<Attributes, TrustError> {
+        if b.len() > MAXATTRIBUTEBYTES { return Err(TrustError::InvalidAttributes) }
+
+        let mut rest = b;
+        let mut last = 0;
+        while !rest.is_empty() {
+            let (attribute, tail) = split_attribute(rest).ok_or(TrustError::InvalidAttributes)?;
+            if attribute.id <= last || !is_well_formed(&attribute) { return Err(TrustError::InvalidAttributes) }
+            last = attribute.id;
+            rest = tail;
+        }
+
+        let mut attributes = Attributes::new();
+        attributes.bytes[..b.len()].copy_from_slice(b);
+        attributes.len = b.len();
+        Ok(attributes)
+    }
+
+    //--- key usage is always marked critical, a verifier that cannot honour it must not accept the link
+    pub fn with_key_usage (self, usage: KeyUsage) -> Result<Attributes, TrustError> {
+        self.with(ATTRIBUTE_KEY_USAGE, true, &[usage.bits()])
+    }
+
+    //--- the number of links that may follow beneath the link's key, critical like key usage
+    pub fn with_max_depth (self, depth: u8) -> Result<Attributes, TrustError> {
+        self.with(ATTRIBUTE_MAX_DEPTH, true, &[depth])
+    }
+
+    pub fn with_label (self, label: &str) -> Result<Attributes, TrustError> {
+        self.with(ATTRIBUTE_LABEL, false, label.as_bytes())
+    }
+
+    //--- fails with InvalidAttributes for ids outside ATTRIBUTE_EXTENSIONS
+    pub fn with_extension (self, id: u8, critical: bool, value: &[u8]) -> Result<Attributes, TrustError> {
+        if !ATTRIBUTE_EXTENSIONS.contains(&id) { return Err(TrustError::InvalidAttributes) }
+        self.with(id, critical, value)
+    }
+
+    fn with (self, id: u8, critical: bool, value: &[u8]) -> Result<Attributes, TrustError> {
+        if value.len() > u8::MAX as usize || self.iter().any(|a| a.id == id) { return Err(TrustError::InvalidAttributes) }
+
+        let added = Attribute { id, critical, value };
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.push(added);
+        entries.sort_by_key(|a| a.id);
+
+        let mut encoded = Vec::with_capacity(self.len + 2 + value.len());
+        for a in entries {
+            encoded.push(if a.critical { a.id | ATTRIBUTE_CRITICAL } else { a.id });
+            encoded.push(a.value.len() as u8);
+            encoded.extend_from_slice(a.value);
+        }
+        Attributes::from_slice(&encoded)
+    }
+
+    pub fn is_empty (&self) -> bool { self.len == 0 }
+
+    pub fn as_bytes (&self) -> &[u8] { &self.bytes[..self.len] }
+
+    pub fn iter (&self) -> impl Iterator<Item = Attribute<'_>> {
+        let mut rest = self.as_bytes();
+        core::iter::from_fn(move || {
+            let (attribute, tail) = split_attribute(rest)?;
+            rest = tail;
+            Some(attribute)
+        })
+    }
+
+    pub fn get (&self, id: u8) -> Option<Attribute<'_>> {
+        self.iter().find(|a| a.id == id)
+    }
+
+    pub fn key_usage (&self) -> Option<KeyUsage> {
+        self.get(ATTRIBUTE_KEY_USAGE).and_then(|a| KeyUsage::from_bits(a.value[0]))
+    }
+
+    pub fn max_depth (&self) -> Option<u8> {
+        self.get(ATTRIBUTE_MAX_DEPTH).map(|a| a.value[0])
+    }
+
+    pub fn label (&self) -> Option<&str> {
+        self.get(ATTRIBUTE_LABEL).and_then(|a| core::str::from_utf8(a.value).ok())
+    }
+
+    //--- the value of an application extension
+    pub fn extension (&self, id: u8) -> Option<&[u8]> {
+        self.get(id).filter(|_| ATTRIBUTE_EXTENSIONS.contains(&id)).map(|a| a.value)
+    }
+
+    //--- A link without a key usage attribute predates roles, and its key may sign both links and data
+    pub fn permits_link_signing (&self) -> bool {
+        self.key_usage().is_none_or(|u| u.can_sign_links())
+    }
+
+    pub fn permits_data_signing (&self) -> bool {
+        self.key_usage().is_none_or(|u| u.can_sign_data())
+    }
+
+    //--- Fails with the id of the first critical attribute that is neither standard nor in `understood`
+    pub fn check_critical (&self, understood: &[u8]) -> Result<(), TrustError> {
+        match self.iter().find(|a| a.critical && !a.is_standard() && !understood.contains(&a.id)) {
+            Some(a) => Err(TrustError::UnknownCriticalAttribute(a.id)),
+            None    => Ok(()),
+        }
+    }
+}
+
+fn split_attribute (b: &[u8]) -> Option<(Attribute<'_>, &[u8])> {
+    let (&kind, rest) = b.split_first()?;
+    let (&len, rest)  = rest.split_first()?;
+    if rest.len() < len as usize { return None }
+    let (value, tail) = rest.split_at(len as usize);
+    Some((Attribute { id: kind & !ATTRIBUTE_CRITICAL, critical: kind & ATTRIBUTE_CRITICAL != 0, value }, tail))
+}
+
+fn is_well_formed (attribute: &Attribute) -> bool {
+    match attribute.id {
+        0                   => false,
+        ATTRIBUTE_KEY_USAGE => attribute.value.len() == 1 && KeyUsage::from_bits(attribute.value[0]).is_some(),
+        ATTRIBUTE_LABEL     => core::str::from_utf8(attribute.value).is_ok_and(|l| !l.is_empty() && !l.chars().any(char::is_control)),
+        ATTRIBUTE_MAX_DEPTH => attribute.value.len() == 1,
+        _                   => true,
+    }
+}
+
+impl Default for Attributes {
+    fn default () -> Attributes { Attributes::new() }
+}
+
+impl PartialEq for Attributes {
+    fn eq(&self, other: &Self) -> bool { self.as_bytes() == other.as_bytes() }
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_should_build_in_canonical_order () {
+        let attributes = Attributes::new()
+            .with_extension(0x41, true, b"tenant-7").unwrap()
+            .with_label("build signer").unwrap()
+            .with_key_usage(KeyUsage::SIGN_DATA).unwrap();
+
+        assert_eq!(attributes.iter().map(|a| a.id()).collect::<Vec<_>>(), vec!(ATTRIBUTE_KEY_USAGE, ATTRIBUTE_LABEL, 0x41));
+        assert_eq!(attributes.key_usage(), Some(KeyUsage::SIGN_DATA));
+        assert_eq!(attributes.label(), Some("build signer"));
+        assert_eq!(attributes.extension(0x41), Some(&b"tenant-7"[..]));
+        assert_eq!(attributes.extension(ATTRIBUTE_LABEL), None);
+        assert_eq!(Attributes::from_slice(attributes.as_bytes()), Ok(attributes));
+        assert_eq!(&attributes.as_bytes()[..3], &[ATTRIBUTE_KEY_USAGE | ATTRIBUTE_CRITICAL, 1, 0x02]);
+
+        assert_eq!(attributes.check_critical(&[]), Err(TrustError::UnknownCriticalAttribute(0x41)));
+        assert_eq!(attributes.check_critical(&[0x41]), Ok(()));
+        assert_eq!(Attributes::new().with_extension(0x41, false, b"").unwrap().check_critical(&[]), Ok(()));
+    }
+
+    #[test]
+    fn malformed_attributes_should_be_rejected () {
+        let too_long = [&[0x41u8, 0xff][..], &[0u8;0xff], &[0x42, 0x00]].concat();
+
+        let cases: Vec<&[u8]> = vec!(
+            &[0x02, 0x01],                  // truncated value
+            &[0x02],                        // truncated header
+            &[0x02, 0x01, b'a', 0x01, 0x01, 0x01], // out of order
+            &[0x41, 0x00, 0xc1, 0x00],      // same id twice, critical or not
+            &[0x00, 0x00],                  // id zero
+            &[0x81, 0x01, 0x00],            // no usage at all
+            &[0x81, 0x01, 0x04],            // undefined usage bit
+            &[0x81, 0x02, 0x01, 0x01],      // usage of the wrong size
+            &[0x83, 0x00],                  // max depth without a value
+            &[0x02, 0x00],                  // empty label
+            &[0x02, 0x01, 0x0a],            // control character in the label
+            &[0x02, 0x01, 0xff],            // label that is not utf8
+            &too_long,
+        );
+
+        for case in cases {
+            assert_eq!(Attributes::from_slice(case), Err(TrustError::InvalidAttributes), "{:02x?}", case);
+        }
+
+        assert_eq!(Attributes::new().with_label("a").and_then(|a| a.with_label("b")), Err(TrustError::InvalidAttributes));
+        assert_eq!(Attributes::new().with_extension(ATTRIBUTE_LABEL, false, b""), Err(TrustError::InvalidAttributes));
+        assert_eq!(Attributes::new().with_extension(0x40, false, &[0u8;256]), Err(TrustError::InvalidAttributes));
+        assert_eq!(Attributes::from_slice(&[0x13, 0x00]).map(|a| a.check_critical(&[])), Ok(Ok(())));
+        assert_eq!(Attributes::from_slice(&[0x93, 0x00]).map(|a| a.check_critical(&[])), Ok(Err(TrustError::UnknownCriticalAttribute(0x13))));
+    }
+}
@@ -0,0 +1,151 @@
+use ring::rand::SystemRandom;
+use ring::signature::Ed25519KeyPair;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::expiry::Expiry;
+use crate::v3::attributes::{Attributes, KeyUsage};
+use crate::v3::link::{Root, Link};
+use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::trust_chain::TrustChain;
+use crate::v3::signer::Signer;
+
+//--- Signs a child key, and its validity window when given, as a link beneath the issuer
+pub fn issue_link (issuer: &dyn Signer, key: PublicKey, expiry: Option<Expiry>) -> Result<Link, TrustError> {
+    issue_link_with_attributes(issuer, key, expiry, Attributes::new())
+}
+
+//--- As issue_link, with the attributes signed alongside the key
+pub fn issue_link_with_attributes (issuer: &dyn Signer, key: PublicKey, expiry: Option<Expiry>, attributes: Attributes) -> Result<Link, TrustError> {
+    let sig = issuer.sign(&Link::signed_data_with_attributes(&key, expiry.as_ref(), &attributes))?;
+    let link = match expiry {
+        Some(expiry) => Link::with_expiry(key, expiry, sig),
+        None         => Link::new(key, sig),
+    };
+    Ok(link.with_attributes(attributes))
+}
+
+fn generate_keypair (rand: &SystemRandom) -> Result<Ed25519KeyPair, TrustError> {
+    Ed25519KeyPair::generate_pkcs8(rand)
+        .ok()
+        .and_then(|pkcs8| Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).ok())
+        .ok_or(TrustError::KeyGenerationFailed)
+}
+
+//--- Issuer side construction of a chain: each intermediate and the end key are generated and signed in turn
+//
+// Intermediates are issued for signing links only and the end key for signing data only.
+pub struct ChainBuilder<'k> {
+    root: &'k dyn Signer,
+    intermediates: Vec<Option<Expiry>>,
+}
+
+impl <'k> ChainBuilder<'k> {
+    pub fn new (root: &'k dyn Signer) -> ChainBuilder<'k> {
+        ChainBuilder { root, intermediates: Vec::new() }
+    }
+
+    pub fn intermediate (mut self, expiry: Option<Expiry>) -> ChainBuilder<'k> {
+        self.intermediates.push(expiry);
+        self
+    }
+
+    pub fn build <'ks> (
+        self,
+        end_expiry: Option<Expiry>,
+        root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+        clock: &dyn Clock ) -> Result<(TrustChain, Ed25519KeyPair), TrustError> {
+
+        let rand         = SystemRandom::new();
+        let mut chain    = TrustChain::new(root_key_store, Root::new(self.root.key()), clock)?;
+        let mut owned    = None;
+        let mut links    = self.intermediates.into_iter().map(|expiry| (expiry, KeyUsage::SIGN_LINKS)).collect::<Vec<_>>();
+        links.push((end_expiry, KeyUsage::SIGN_DATA));
+
+        for (expiry, usage) in links {
+            let keypair = generate_keypair(&rand)?;
+            let issuer  = owned.as_ref().map(|k| k as &dyn Signer).unwrap_or(self.root);
+            let usage   = Attributes::new().with_key_usage(usage)?;
+            chain       = chain.append(issue_link_with_attributes(issuer, keypair.key(), expiry, usage)?, clock)?;
+            owned       = Some(keypair);
+        }
+
+        // safe because the end key is always issued above.
+        Ok((chain, owned.unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::expiry::{NotBefore, NotAfter};
+    use crate::v3::clock::{SystemClock, FixedClock};
+    use crate::v3::signature::Signature;
+    use super::*;
+
+    fn root_keypair () -> Ed25519KeyPair {
+        generate_keypair(&SystemRandom::new()).unwrap()
+    }
+
+    #[test]
+    fn builder_should_issue_end_key () {
+        let root_keypair   = root_keypair();
+        let root_key_store = vec!(root_keypair.key());
+        let (chain, end)   = ChainBuilder::new(&root_keypair).build(None, &root_key_store, &SystemClock).unwrap();
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.first().key(), root_keypair.key());
+        assert_eq!(chain.last().key(),  end.key());
+
+        let sig            = Signature::from(end.sign(b"test data"));
+
+        assert_eq!(chain.verify_data(&sig, b"test data", &SystemClock), Ok(()));
+    }
+
+    #[test]
+    fn builder_should_issue_intermediates () {
+        let root_keypair   = root_keypair();
+        let root_key_store = vec!(root_keypair.key());
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let clock          = FixedClock::new(1_500);
+        let (chain, end)   = ChainBuilder::new(&root_keypair)
+            .intermediate(Some(expiry))
+            .intermediate(None)
+            .intermediate(None)
+            .build(Some(expiry), &root_key_store, &clock)
+            .unwrap();
+
+        assert_eq!(chain.len(), 5);
+        assert_eq!(chain.iter().nth(1).and_then(|l| l.expiry()), Some(expiry));
+        assert_eq!(chain.iter().nth(2).and_then(|l| l.expiry()), None);
+        assert_eq!(chain.last().key(), end.key());
+        assert_eq!(chain.last().expiry(), Some(expiry));
+
+        let chain_bytes    = chain.as_bytes();
+        let parsed_chain   = crate::v3::parsers::trust_chain(&mut chain_bytes.as_slice(), &root_key_store, &clock);
+
+        assert_eq!(parsed_chain, Ok(chain));
+    }
+
+    #[test]
+    fn builder_should_respect_chain_limits () {
+        let root_keypair   = root_keypair();
+        let root_key_store = vec!(root_keypair.key());
+        let too_long       = ChainBuilder::new(&root_keypair)
+            .intermediate(None)
+            .intermediate(None)
+            .intermediate(None)
+            .intermediate(None)
+            .build(None, &root_key_store, &SystemClock);
+
+        assert_eq!(too_long.map(|(chain, _)| chain), Err(TrustError::MaxChainLengthExceeded));
+
+        let untrusted      = ChainBuilder::new(&root_keypair).build(None, &Vec::<PublicKey>::new(), &SystemClock);
+
+        assert_eq!(untrusted.map(|(chain, _)| chain), Err(TrustError::NoRootKeyTrust));
+
+        let expired        = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let stale          = ChainBuilder::new(&root_keypair).build(Some(expired), &root_key_store, &SystemClock);
+
+        assert_eq!(stale.map(|(chain, _)| chain), Err(TrustError::InvalidExpiry));
+    }
+}
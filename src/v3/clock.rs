@@ -0,0 +1,53 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//--- The source of "now" used when checking link validity windows, in seconds since the Unix epoch
+pub trait Clock {
+    fn now (&self) -> u64;
+}
+
+#[derive(Copy,Clone,Debug,Default,PartialEq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now (&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+    }
+}
+
+//--- A clock stuck at a single instant, for tests and for checking signatures "as of" a past time
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct FixedClock(u64);
+
+impl FixedClock {
+    pub fn new (at: u64) -> FixedClock {
+        FixedClock(at)
+    }
+}
+
+impl Clock for FixedClock {
+    fn now (&self) -> u64 { self.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::*;
+
+    #[test]
+    fn system_clock_should_track_wall_time () {
+        let before = Utc::now().timestamp() as u64;
+        let now    = SystemClock.now();
+        let after  = Utc::now().timestamp() as u64;
+
+        assert!(before <= now && now <= after);
+    }
+
+    #[test]
+    fn fixed_clock_should_not_move () {
+        let at    = Utc.with_ymd_and_hms(2020, 1, 14, 0, 0, 0).unwrap().timestamp() as u64;
+        let clock = FixedClock::new(at);
+
+        assert_eq!(clock.now(), 1_578_960_000);
+        assert_eq!(clock.now(), clock.now());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::v3::{PUBLICKEYBYTES,SIGNATUREBYTES,EXPIRYBYTES,MAXATTRIBUTEBYTES,TC_V3_HEADER,TC_V3_FLAGS_HEADER};
+use crate::v3::link::{Link, LINK_FLAG_EXPIRY, LINK_FLAG_ATTRIBUTES, LINK_FLAGS};
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::expiry::Expiry;
+use crate::v3::attributes::Attributes;
+
+use std::io::{self, Read};
+
+// a link carrying every optional field, attribute block at its largest.
+pub(crate) const MAX_LINK_LEN: usize = PUBLICKEYBYTES + 1 + EXPIRYBYTES + 2 + MAXATTRIBUTEBYTES + SIGNATUREBYTES;
+
+//--- How the links of a chain are laid out
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub(crate) enum LinkLayout {
+    // key and signature only, as v3 chains were written before links had flags.
+    Plain,
+    // a flags byte follows the key, which may set only the given bits.
+    Flagged(u8),
+}
+
+//--- The layout of a v3 chain's links, going by its version word
+// Chains written before links had flags keep the plain v3 word and layout, so they still parse as they are.
+pub(crate) fn link_layout (header: &[u8]) -> Result<LinkLayout, TrustError> {
+    if header == TC_V3_HEADER { return Ok(LinkLayout::Plain) }
+    if header == TC_V3_FLAGS_HEADER { return Ok(LinkLayout::Flagged(LINK_FLAGS)) }
+    Err(TrustError::InvalidTrustChain)
+}
+
+//--- One encoded link, its fields borrowed from the bytes they were decoded from
+pub(crate) struct EncodedLink<'a> {
+    pub(crate) key: &'a [u8],
+    pub(crate) expiry: Option<Expiry>,
+    pub(crate) attributes: &'a [u8],
+    pub(crate) parsed: Attributes,
+    pub(crate) sig: &'a [u8],
+    // how many bytes of the input the link took up.
+    pub(crate) len: usize,
+}
+
+impl <'a> EncodedLink<'a> {
+    // safe because decode_link only hands out slices of the exact key and signature lengths.
+    pub(crate) fn public_key (&self) -> PublicKey { PublicKey::from_slice(self.key).unwrap() }
+
+    pub(crate) fn signature (&self) -> Signature { Signature::from_slice(self.sig).unwrap() }
+
+    pub(crate) fn to_link (&self) -> Link {
+        let link = match self.expiry {
+            Some(expiry) => Link::with_expiry(self.public_key(), expiry, self.signature()),
+            None         => Link::new(self.public_key(), self.signature()),
+        };
+        link.with_attributes(self.parsed)
+    }
+}
+
+// a decoded link is taken apart as soon as it is returned, so boxing it would only add an allocation.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Decoded<'a> {
+    Link(EncodedLink<'a>),
+    // the input ends inside the link, which needs at least this many bytes before decoding can go on.
+    Needs(usize),
+}
+
+//--- Decodes the link at the start of the input, checking each field as soon as its bytes are there
+//
+// Every parser goes through here, so they all fail alike on the same input. Slice parsers treat Needs
+// as the end of their input, stream parsers read up to what it asks for and decode again.
+pub(crate) fn decode_link (input: &[u8], layout: LinkLayout) -> Result<Decoded<'_>, TrustError> {
+    let mut at = 0;
+    macro_rules! field {
+        ($len:expr) => {{
+            at += $len;
+            match input.get(at - $len..at) {
+                Some(bytes) => bytes,
+                None        => return Ok(Decoded::Needs(at)),
+            }
+        }};
+    }
+
+    let key   = field!(PUBLICKEYBYTES);
+    let flags = match layout {
+        LinkLayout::Plain            => 0,
+        LinkLayout::Flagged(allowed) => {
+            let flags = field!(1)[0];
+            if flags & !allowed != 0 { return Err(TrustError::InvalidTrustChain) }
+            flags
+        },
+    };
+
+    let expiry = if flags & LINK_FLAG_EXPIRY != 0 {
+        Some(Expiry::from_slice(field!(EXPIRYBYTES)).ok_or(TrustError::InvalidExpiry)?)
+    } else {
+        None
+    };
+
+    let (attributes, parsed) = if flags & LINK_FLAG_ATTRIBUTES != 0 {
+        let len = field!(2);
+        let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        // an empty block is left out rather than flagged, so a link has only the one encoding.
+        if len == 0 || len > MAXATTRIBUTEBYTES { return Err(TrustError::InvalidAttributes) }
+        let block = field!(len);
+        (block, Attributes::from_slice(block)?)
+    } else {
+        (&[][..], Attributes::new())
+    };
+
+    let sig = field!(SIGNATUREBYTES);
+    Ok(Decoded::Link(EncodedLink { key, expiry, attributes, parsed, sig, len: at }))
+}
+
+// read_exact keeps reading across short reads and interruptions, so a chain split over several
+// reads of a pipe, socket or BufReader is put back together before any field is decoded.
+#[inline]
+pub(crate) fn read_field <R> (input: &mut R, buf: &mut [u8]) -> Result<(), TrustError>
+where R: Read  {
+    input.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => TrustError::UnexpectedEof,
+        kind => TrustError::ReadFailed(kind),
+    })
+}
+
+//--- Reads one link off the stream, never past its end, and hands it to `build`
+pub(crate) fn read_link_with <R, T> (input: &mut R, layout: LinkLayout, build: impl FnOnce(&EncodedLink) -> T) -> Result<T, TrustError>
+where R: Read  {
+    let mut buf  = [0u8;MAX_LINK_LEN];
+    let mut have = 0;
+    loop {
+        match decode_link(&buf[..have], layout)? {
+            Decoded::Link(link) => return Ok(build(&link)),
+            Decoded::Needs(len) => {
+                read_field(input, &mut buf[have..len])?;
+                have = len;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::expiry::{NotBefore, NotAfter};
+    use super::*;
+
+    fn needs (input: &[u8], layout: LinkLayout) -> Option<usize> {
+        match decode_link(input, layout) {
+            Ok(Decoded::Needs(len)) => Some(len),
+            _                       => None,
+        }
+    }
+
+    #[test]
+    fn decode_link_should_ask_for_one_field_at_a_time () {
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let mut encoded    = vec!(0x11;PUBLICKEYBYTES);
+        encoded.push(LINK_FLAG_EXPIRY);
+        encoded.extend_from_slice(&expiry.as_bytes());
+        encoded.extend_from_slice(&[0x22;SIGNATUREBYTES]);
+        let layout         = LinkLayout::Flagged(LINK_FLAGS);
+
+        assert_eq!(needs(&[], layout), Some(PUBLICKEYBYTES));
+        assert_eq!(needs(&encoded[..PUBLICKEYBYTES], layout), Some(PUBLICKEYBYTES + 1));
+        assert_eq!(needs(&encoded[..PUBLICKEYBYTES + 1], layout), Some(PUBLICKEYBYTES + 1 + EXPIRYBYTES));
+        assert_eq!(needs(&encoded[..encoded.len() - 1], layout), Some(encoded.len()));
+
+        match decode_link(&[&encoded[..], &[0x33]].concat(), layout) {
+            Ok(Decoded::Link(link)) => {
+                assert_eq!(link.len, encoded.len());
+                assert_eq!(link.expiry, Some(expiry));
+                assert_eq!(link.sig, &[0x22;SIGNATUREBYTES][..]);
+            },
+            _ => panic!("link should decode"),
+        }
+
+        // the plain layout has no flags byte, and a flags byte may only set what the layout allows.
+        assert_eq!(needs(&encoded[..PUBLICKEYBYTES], LinkLayout::Plain), Some(PUBLICKEYBYTES + SIGNATUREBYTES));
+        assert!(matches!(decode_link(&encoded, LinkLayout::Flagged(0)), Err(TrustError::InvalidTrustChain)));
+    }
+}
@@ -0,0 +1,350 @@
+use crate::v3::PUBLICKEYBYTES;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::fingerprint::Fingerprint;
+use crate::v3::expiry::{Expiry, NotBefore, NotAfter};
+use crate::v3::anchor::TrustAnchor;
+use crate::v3::keystore::{RootKeysStore, find_anchor};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// anchor files are a few hundred bytes at most, anything much larger was dropped in by mistake.
+const MAX_ANCHOR_FILE_BYTES : u64 = 16 * 1024;
+
+//--- One trust anchor loaded from a file, named after the file unless it carries a Name header
+#[derive(Clone,Debug,PartialEq)]
+pub struct AnchorFile {
+    pub path: PathBuf,
+    pub anchor: TrustAnchor,
+    pub comment: Option<String>,
+}
+
+//--- A file in the directory that did not yield a key, and why
+#[derive(Clone,Debug,PartialEq)]
+pub struct AnchorFailure {
+    pub path: PathBuf,
+    pub error: TrustError,
+}
+
+//--- Root keys read from a directory such as /etc/trustchain/roots.d, one key per file
+//
+// Each regular file that is not hidden holds one key, as
+//  - exactly 32 raw bytes,
+//  - a hex line, optionally preceded by `Header: value` lines and `#` comment lines, or
+//  - an armored TRUST CHAIN PUBLIC KEY block with the same headers (needs the armor feature).
+//
+// The headers describe the anchor: Name, Comment, Not-Before and Not-After (unix seconds, both or
+// neither), Max-Depth, and Distrusted (yes or no). Any other header fails the file. When several files
+// hold the same key, a distrusted one wins over the others, and otherwise the first in name order.
+// A file whose name is not utf8 fails as well, so a dropped-in root is never ignored without a trace.
+#[derive(Clone,Debug)]
+pub struct DirectoryKeysStore {
+    dir: PathBuf,
+    anchors: Vec<AnchorFile>,
+    failures: Vec<AnchorFailure>,
+}
+
+impl DirectoryKeysStore {
+    pub fn open <P: AsRef<Path>> (dir: P) -> io::Result<DirectoryKeysStore> {
+        let mut store = DirectoryKeysStore { dir: dir.as_ref().to_path_buf(), anchors: Vec::new(), failures: Vec::new() };
+        store.reload()?;
+        Ok(store)
+    }
+
+    //--- Re-reads the whole directory. Files that fail to parse are left out and reported by failures();
+    // if the directory itself cannot be listed the previously loaded keys are kept and the error returned.
+    pub fn reload (&mut self) -> io::Result<()> {
+        let mut paths = fs::read_dir(&self.dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.sort();
+
+        let mut anchors  = Vec::new();
+        let mut failures = Vec::new();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default();
+            if name.as_encoded_bytes().starts_with(b".") || !path.is_file() { continue }
+
+            // anchors are named after their file, so a name that is not utf8 fails the file rather than hiding it.
+            if name.to_str().is_none() {
+                failures.push(AnchorFailure { path, error: TrustError::InvalidKeyEncoding });
+                continue
+            }
+
+            match load_anchor(&path) {
+                Ok(anchor) => anchors.push(anchor),
+                Err(error) => failures.push(AnchorFailure { path, error }),
+            }
+        }
+
+        self.anchors  = anchors;
+        self.failures = failures;
+        Ok(())
+    }
+
+    pub fn dir (&self) -> &Path { &self.dir }
+
+    pub fn anchors (&self) -> &[AnchorFile] { &self.anchors }
+
+    pub fn failures (&self) -> &[AnchorFailure] { &self.failures }
+
+    pub fn keys (&self) -> Vec<PublicKey> {
+        self.anchors.iter().map(|a| a.anchor.key()).collect()
+    }
+}
+
+impl <'ks> RootKeysStore<'ks> for DirectoryKeysStore {
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor> {
+        find_anchor(self.anchors.iter().map(|a| &a.anchor), candidate)
+    }
+
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.anchors.iter().map(|a| a.anchor.key()).find(|k| k.fingerprint() == *fingerprint)
+    }
+}
+
+fn load_anchor (path: &Path) -> Result<AnchorFile, TrustError> {
+    let read_failed = |e: io::Error| TrustError::ReadFailed(e.kind());
+    if fs::metadata(path).map_err(read_failed)?.len() > MAX_ANCHOR_FILE_BYTES {
+        return Err(TrustError::InvalidKeyEncoding)
+    }
+    let bytes = fs::read(path).map_err(read_failed)?;
+    let stem  = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+
+    if bytes.len() == PUBLICKEYBYTES {
+        // safe because the length was just checked.
+        let key = PublicKey::from_slice(&bytes).unwrap();
+        return Ok(AnchorFile { path: path.to_path_buf(), anchor: TrustAnchor::new(key).with_name(&stem), comment: None })
+    }
+
+    let text = std::str::from_utf8(&bytes).map_err(|_| TrustError::InvalidKeyEncoding)?;
+    let (key, headers) = if text.trim_start().starts_with("-----BEGIN ") {
+        armored_anchor(text)?
+    } else {
+        hex_anchor(text)?
+    };
+    let (anchor, comment) = apply_headers(TrustAnchor::new(key).with_name(&stem), &headers)?;
+    Ok(AnchorFile { path: path.to_path_buf(), anchor, comment })
+}
+
+fn hex_anchor (text: &str) -> Result<(PublicKey, Vec<(String,String)>), TrustError> {
+    let mut key     = None;
+    let mut headers = Vec::new();
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        match line.split_once(':') {
+            Some((h, v))          => headers.push((h.trim().to_string(), v.trim().to_string())),
+            None if key.is_none() => key = Some(line.parse::<PublicKey>()?),
+            _ => return Err(TrustError::InvalidKeyEncoding),
+        }
+    }
+    Ok((key.ok_or(TrustError::InvalidKeyEncoding)?, headers))
+}
+
+#[cfg(feature = "armor")]
+fn armored_anchor (text: &str) -> Result<(PublicKey, Vec<(String,String)>), TrustError> {
+    let key   = crate::v3::armor::armored_public_key(text)?;
+    let armor = crate::v3::armor::Armor::parse(text)?;
+    // the armor's own Fingerprint header has already been checked against the key.
+    let headers = armor.headers().iter().filter(|(h, _)| !h.eq_ignore_ascii_case("Fingerprint")).cloned().collect();
+    Ok((key, headers))
+}
+
+#[cfg(not(feature = "armor"))]
+fn armored_anchor (_text: &str) -> Result<(PublicKey, Vec<(String,String)>), TrustError> {
+    Err(TrustError::InvalidArmor)
+}
+
+fn apply_headers (mut anchor: TrustAnchor, headers: &[(String,String)]) -> Result<(TrustAnchor, Option<String>), TrustError> {
+    let mut comment    = None;
+    let mut not_before = None;
+    let mut not_after  = None;
+    let mut seen       = Vec::new();
+    let number         = |v: &str| v.parse::<u64>().map_err(|_| TrustError::InvalidKeyEncoding);
+
+    for (header, value) in headers {
+        let header = header.to_ascii_lowercase();
+        if seen.contains(&header) { return Err(TrustError::InvalidKeyEncoding) }
+        match header.as_str() {
+            "name"       => anchor     = anchor.with_name(value),
+            "comment"    => comment    = Some(value.clone()),
+            "not-before" => not_before = Some(NotBefore(number(value)?)),
+            "not-after"  => not_after  = Some(NotAfter(number(value)?)),
+            "max-depth"  => anchor     = anchor.with_max_depth(value.parse::<u8>().map_err(|_| TrustError::InvalidKeyEncoding)?),
+            "distrusted" => anchor     = anchor.with_distrusted(match value.to_ascii_lowercase().as_str() {
+                "yes" | "true" => true,
+                "no" | "false" => false,
+                _ => return Err(TrustError::InvalidKeyEncoding),
+            }),
+            _ => return Err(TrustError::InvalidKeyEncoding),
+        }
+        seen.push(header);
+    }
+
+    match (not_before, not_after) {
+        (Some(nb), Some(na)) => anchor = anchor.with_validity(Expiry::new(nb, na)?),
+        (None, None)         => (),
+        _                    => return Err(TrustError::InvalidKeyEncoding),
+    }
+    Ok((anchor, comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new (name: &str) -> TempDir {
+            let dir = std::env::temp_dir().join(format!("trustchain-roots-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn write (&self, file: &str, contents: &[u8]) {
+            fs::write(self.0.join(file), contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop (&mut self) { let _ = fs::remove_dir_all(&self.0); }
+    }
+
+    fn key (fill: u8) -> PublicKey {
+        PublicKey::from_slice(&[fill;PUBLICKEYBYTES]).unwrap()
+    }
+
+    #[test]
+    fn store_should_load_raw_and_hex_anchors () {
+        let dir    = TempDir::new("load");
+        dir.write("a-raw.key", key(0x01).as_ref());
+        dir.write("b-hex.key", format!("# rotated 2026-10\nName: prod-2026\ncomment: offline HSM\n{}\n", key(0x02)).as_bytes());
+        dir.write("c-bare.key", format!("{}\n", key(0x03)).as_bytes());
+        dir.write(".hidden", key(0x04).as_ref());
+        fs::create_dir(dir.0.join("subdir")).unwrap();
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+
+        assert_eq!(store.keys(), vec!(key(0x01), key(0x02), key(0x03)));
+        assert_eq!(store.failures(), &[]);
+        assert_eq!(store.anchors()[0].anchor.name(), Some("a-raw"));
+        assert_eq!(store.anchors()[1].anchor.name(), Some("prod-2026"));
+        assert_eq!(store.anchors()[1].comment.as_deref(), Some("offline HSM"));
+        assert!(store.contains_root_key(key(0x02).as_ref()));
+        assert!(!store.contains_root_key(key(0x04).as_ref()));
+        assert_eq!(store.root_key_by_fingerprint(&key(0x03).fingerprint()), Some(key(0x03)));
+    }
+
+    #[test]
+    fn store_should_report_files_that_fail_to_parse () {
+        let dir    = TempDir::new("failures");
+        dir.write("good.key", key(0x01).as_ref());
+        dir.write("short.key", &key(0x02).as_ref()[1..]);
+        dir.write("two-keys.key", format!("{}\n{}\n", key(0x02), key(0x03)).as_bytes());
+        dir.write("unknown-header.key", format!("Owner: ops\n{}\n", key(0x02)).as_bytes());
+        dir.write("huge.key", &vec!(b'#'; MAX_ANCHOR_FILE_BYTES as usize + 1));
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+        let failed = store.failures().iter().map(|f| f.path.file_name().unwrap().to_str().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(store.keys(), vec!(key(0x01)));
+        assert_eq!(failed, vec!("huge.key", "short.key", "two-keys.key", "unknown-header.key"));
+        assert!(store.failures().iter().all(|f| f.error == TrustError::InvalidKeyEncoding));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn store_should_report_files_whose_names_are_not_utf8 () {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir    = TempDir::new("non-utf8");
+        let name   = OsStr::from_bytes(b"root-\xff.key");
+        dir.write("good.key", key(0x01).as_ref());
+        fs::write(dir.0.join(name), key(0x02).as_ref()).unwrap();
+        fs::write(dir.0.join(OsStr::from_bytes(b".hidden-\xff")), key(0x03).as_ref()).unwrap();
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+
+        assert_eq!(store.keys(), vec!(key(0x01)));
+        assert_eq!(store.failures(), &[AnchorFailure { path: dir.0.join(name), error: TrustError::InvalidKeyEncoding }]);
+    }
+
+    #[test]
+    fn anchor_headers_should_set_the_anchor_terms () {
+        let dir    = TempDir::new("headers");
+        dir.write("bounded.key", format!("Not-Before: 1000\nNot-After: 2000\nMax-Depth: 1\n{}\n", key(0x01)).as_bytes());
+        dir.write("retired.key", format!("{}\nDistrusted: yes\n", key(0x02)).as_bytes());
+        dir.write("half-validity.key", format!("Not-After: 2000\n{}\n", key(0x03)).as_bytes());
+        dir.write("bad-depth.key", format!("Max-Depth: 256\n{}\n", key(0x03)).as_bytes());
+        dir.write("twice.key", format!("Name: a\nName: b\n{}\n", key(0x03)).as_bytes());
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+        let failed = store.failures().iter().map(|f| f.path.file_name().unwrap().to_str().unwrap()).collect::<Vec<_>>();
+        let bound  = store.trust_anchor(key(0x01).as_ref()).unwrap();
+
+        assert_eq!(store.keys(), vec!(key(0x01), key(0x02)));
+        assert_eq!(failed, vec!("bad-depth.key", "half-validity.key", "twice.key"));
+        assert_eq!(bound.max_depth(), Some(1));
+        assert_eq!(bound.admits_at(1_500), Ok(()));
+        assert_eq!(bound.admits_at(2_001), Err(TrustError::InvalidExpiry));
+        assert!(store.trust_anchor(key(0x02).as_ref()).unwrap().is_distrusted());
+        assert!(!store.contains_root_key(key(0x02).as_ref()));
+    }
+
+    #[test]
+    fn distrusted_duplicate_should_win_over_earlier_files () {
+        let dir    = TempDir::new("duplicates");
+        dir.write("a-root.key", format!("Name: root\n{}\n", key(0x01)).as_bytes());
+        dir.write("z-retired.key", format!("Distrusted: yes\n{}\n", key(0x01)).as_bytes());
+        dir.write("b-twice.key", format!("Name: first\n{}\n", key(0x02)).as_bytes());
+        dir.write("c-twice.key", format!("Name: second\n{}\n", key(0x02)).as_bytes());
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+
+        assert!(store.trust_anchor(key(0x01).as_ref()).unwrap().is_distrusted());
+        assert!(!store.contains_root_key(key(0x01).as_ref()));
+        assert_eq!(store.trust_anchor(key(0x02).as_ref()).unwrap().name(), Some("first"));
+    }
+
+    #[test]
+    fn reload_should_pick_up_rolled_keys () {
+        let dir    = TempDir::new("reload");
+        dir.write("old.key", key(0x01).as_ref());
+        let mut store = DirectoryKeysStore::open(&dir.0).unwrap();
+
+        dir.write("new.key", key(0x02).as_ref());
+        fs::remove_file(dir.0.join("old.key")).unwrap();
+
+        assert!(store.contains_root_key(key(0x01).as_ref()));
+
+        store.reload().unwrap();
+
+        assert_eq!(store.keys(), vec!(key(0x02)));
+
+        fs::remove_dir_all(&dir.0).unwrap();
+
+        assert!(store.reload().is_err());
+        assert_eq!(store.keys(), vec!(key(0x02)));
+        assert!(DirectoryKeysStore::open(&dir.0).is_err());
+    }
+
+    #[cfg(feature = "armor")]
+    #[test]
+    fn store_should_load_armored_anchors () {
+        let dir    = TempDir::new("armor");
+        let armor  = key(0x01).to_armor().header("Name", "armored-root").unwrap().header("Comment", "from the vault").unwrap();
+        dir.write("root.asc", armor.to_string().as_bytes());
+        dir.write("wrong.asc", key(0x02).to_armor().to_string().replace(&key(0x02).fingerprint().to_string(), &key(0x03).fingerprint().to_string()).as_bytes());
+
+        let store  = DirectoryKeysStore::open(&dir.0).unwrap();
+
+        assert_eq!(store.keys(), vec!(key(0x01)));
+        assert_eq!(store.anchors()[0].anchor.name(), Some("armored-root"));
+        assert_eq!(store.anchors()[0].comment.as_deref(), Some("from the vault"));
+        assert_eq!(store.failures(), &[AnchorFailure { path: dir.0.join("wrong.asc"), error: TrustError::InvalidArmor }]);
+    }
+}
@@ -1,10 +1,30 @@
+use crate::v3::key::PublicKey;
+use std::io;
+
 #[derive(Copy,Clone,Debug,PartialEq)]
 pub enum TrustError {
     NoRootKeyTrust,
     InvalidSignature,
     InvalidExpiry,
     MaxChainLengthExceeded,
-    InvalidTrustChain
+    InvalidTrustChain,
+    InvalidRevocationList,
+    UnexpectedEof,
+    TrailingData,
+    ReadFailed(io::ErrorKind),
+    RevokedKey(PublicKey),
+    KeyGenerationFailed,
+    InvalidSecretKey,
+    KeyDecryptionFailed,
+    SigningFailed,
+    InvalidArmor,
+    InvalidKeyEncoding,
+    DistrustedAnchor,
+    PathLengthExceeded,
+    InvalidAttributes,
+    UnknownCriticalAttribute(u8),
+    LinkSigningNotPermitted,
+    DataSigningNotPermitted,
 }
 
 
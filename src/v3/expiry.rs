@@ -0,0 +1,77 @@
+use crate::v3::EXPIRYBYTES;
+use crate::v3::error::TrustError;
+use core::convert::TryInto;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct NotBefore(pub u64);
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct NotAfter(pub u64);
+
+//--- Inclusive validity window of a link, in seconds since the Unix epoch
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Expiry {
+    not_before: NotBefore,
+    not_after: NotAfter,
+}
+
+impl Expiry {
+    pub fn new (not_before: NotBefore, not_after: NotAfter) -> Result<Expiry, TrustError> {
+        if not_before.0 > not_after.0 { return Err(TrustError::InvalidExpiry) }
+        Ok(Expiry {not_before, not_after})
+    }
+
+    pub fn from_slice (b:&[u8]) -> Option<Expiry> {
+        if b.len() != EXPIRYBYTES { return None }
+
+        let not_before = u64::from_be_bytes(b[0..8].try_into().ok()?);
+        let not_after  = u64::from_be_bytes(b[8..16].try_into().ok()?);
+        Expiry::new(NotBefore(not_before), NotAfter(not_after)).ok()
+    }
+
+    pub fn not_before (&self) -> NotBefore { self.not_before }
+
+    pub fn not_after (&self) -> NotAfter { self.not_after }
+
+    pub fn is_valid_at (&self, at: u64) -> bool {
+        self.not_before.0 <= at && at <= self.not_after.0
+    }
+
+    pub fn as_bytes (&self) -> [u8;EXPIRYBYTES] {
+        let mut out = [0u8;EXPIRYBYTES];
+        out[0..8].copy_from_slice(&self.not_before.0.to_be_bytes());
+        out[8..16].copy_from_slice(&self.not_after.0.to_be_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_should_reject_inverted_window () {
+        assert_eq!(Expiry::new(NotBefore(10), NotAfter(9)), Err(TrustError::InvalidExpiry));
+        assert!(Expiry::new(NotBefore(10), NotAfter(10)).is_ok());
+    }
+
+    #[test]
+    fn expiry_window_is_inclusive () {
+        let expiry = Expiry::new(NotBefore(10), NotAfter(20)).unwrap();
+
+        assert!(!expiry.is_valid_at(9));
+        assert!( expiry.is_valid_at(10));
+        assert!( expiry.is_valid_at(20));
+        assert!(!expiry.is_valid_at(21));
+    }
+
+    #[test]
+    fn expiry_bytes_should_round_trip () {
+        let expiry = Expiry::new(NotBefore(1_579_000_000), NotAfter(1_579_086_400)).unwrap();
+        let bytes  = expiry.as_bytes();
+
+        assert_eq!(bytes[0..8], 1_579_000_000u64.to_be_bytes());
+        assert_eq!(Expiry::from_slice(&bytes), Some(expiry));
+        assert_eq!(Expiry::from_slice(&bytes[1..]), None);
+    }
+}
@@ -0,0 +1,216 @@
+use ring::digest;
+use crate::v3::FINGERPRINTBYTES;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use core::fmt;
+use core::str::FromStr;
+
+// Hashed ahead of the key so a fingerprint can never collide with a digest of the same 32 bytes used elsewhere.
+const FINGERPRINT_DOMAIN : &[u8] = b"trustchain v3 key fingerprint\x00";
+const FINGERPRINT_PREFIX : &str  = "tc3-sha256:";
+
+// rfc4648 base32 alphabet, lowercased; it has no 0/1/8 so it reads out loud without ambiguity.
+const BASE32 : &[u8;32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+const SHORT_BYTES : usize = 10;
+
+//--- SHA-256 over a domain tag and the key bytes, shown as `tc3-sha256:` followed by base32
+#[derive(Copy,Clone,PartialEq,Eq,Hash)]
+pub struct Fingerprint([u8;FINGERPRINTBYTES]);
+
+impl Fingerprint {
+    pub fn of (key: &PublicKey) -> Fingerprint {
+        let mut ctx = digest::Context::new(&digest::SHA256);
+        ctx.update(FINGERPRINT_DOMAIN);
+        ctx.update(key.as_ref());
+        let mut fp = Fingerprint([0u8;FINGERPRINTBYTES]);
+        fp.0.copy_from_slice(ctx.finish().as_ref());
+        fp
+    }
+
+    //--- the first 80 bits as four dash separated groups of base32, e.g. `mfrg-gzdf-mztw-q2lk`
+    pub fn short (&self) -> String {
+        let encoded = base32(&self.0[..SHORT_BYTES]);
+        encoded.as_bytes()
+            .chunks(4)
+            .map(|c| core::str::from_utf8(c).unwrap())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    //--- OpenSSH style "drunken bishop" picture of the fingerprint, easier to compare at a glance than text
+    pub fn randomart (&self) -> String {
+        const WIDTH  : usize = 17;
+        const HEIGHT : usize = 9;
+        const SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^";
+
+        let mut field = [[0usize;WIDTH];HEIGHT];
+        let (mut x, mut y) = (WIDTH / 2, HEIGHT / 2);
+
+        for byte in self.0.iter() {
+            for step in 0..4 {
+                let bits = byte >> (step * 2);
+                x = if bits & 0x01 != 0 { (x + 1).min(WIDTH - 1) } else { x.saturating_sub(1) };
+                y = if bits & 0x02 != 0 { (y + 1).min(HEIGHT - 1) } else { y.saturating_sub(1) };
+                field[y][x] += 1;
+            }
+        }
+
+        let mut art = border("[ED25519 256]", WIDTH);
+        for (row, cells) in field.iter().enumerate() {
+            art.push('|');
+            for (col, count) in cells.iter().enumerate() {
+                art.push(match (col, row) {
+                    // as in OpenSSH, the end mark wins when the walk finishes where it started.
+                    (c, r) if (c, r) == (x, y)                  => 'E',
+                    (c, r) if (c, r) == (WIDTH / 2, HEIGHT / 2) => 'S',
+                    _ => SYMBOLS[(*count).min(SYMBOLS.len() - 1)] as char,
+                });
+            }
+            art.push_str("|\n");
+        }
+        art.push_str(&border("[TC3-SHA256]", WIDTH));
+        art
+    }
+}
+
+fn border (title: &str, width: usize) -> String {
+    let left = (width - title.len()) / 2;
+    format!("+{}{}{}+\n", "-".repeat(left), title, "-".repeat(width - left - title.len()))
+}
+
+fn base32 (bytes: &[u8]) -> String {
+    let mut out  = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut acc  = 0u16;
+    let mut bits = 0;
+    for b in bytes {
+        acc   = (acc << 8) | *b as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[(acc >> bits) as usize & 0x1f] as char);
+        }
+    }
+    if bits > 0 { out.push(BASE32[(acc << (5 - bits)) as usize & 0x1f] as char) }
+    out
+}
+
+// strict: unused trailing bits have to be zero, so every fingerprint has exactly one spelling (case aside).
+fn unbase32 (s: &str) -> Option<Vec<u8>> {
+    let mut out  = Vec::with_capacity(s.len() * 5 / 8);
+    let mut acc  = 0u16;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = BASE32.iter().position(|a| *a == c.to_ascii_lowercase())? as u16;
+        acc   = (acc << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 { return None }
+    Some(out)
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", FINGERPRINT_PREFIX, base32(&self.0))
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fingerprint({})", self)
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = TrustError;
+
+    fn from_str (s: &str) -> Result<Fingerprint, TrustError> {
+        let s = s.trim();
+        let encoded = s.get(..FINGERPRINT_PREFIX.len())
+            .filter(|p| p.eq_ignore_ascii_case(FINGERPRINT_PREFIX))
+            .map(|_| &s[FINGERPRINT_PREFIX.len()..])
+            .ok_or(TrustError::InvalidKeyEncoding)?;
+        let bytes = unbase32(encoded).filter(|b| b.len() == FINGERPRINTBYTES).ok_or(TrustError::InvalidKeyEncoding)?;
+        let mut fp = Fingerprint([0u8;FINGERPRINTBYTES]);
+        fp.0.copy_from_slice(&bytes);
+        Ok(fp)
+    }
+}
+
+impl AsRef<[u8]> for Fingerprint {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl PublicKey {
+    pub fn fingerprint (&self) -> Fingerprint { Fingerprint::of(self) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::PUBLICKEYBYTES;
+    use crate::v3::keystore::RootKeysStore;
+    use super::*;
+
+    fn key (fill: u8) -> PublicKey {
+        PublicKey::from_slice(&[fill;PUBLICKEYBYTES]).unwrap()
+    }
+
+    #[test]
+    fn fingerprint_should_be_stable_and_domain_separated () {
+        let fp     = key(0x00).fingerprint();
+        let plain  = digest::digest(&digest::SHA256, key(0x00).as_ref());
+
+        assert_eq!(fp, key(0x00).fingerprint());
+        assert_ne!(fp, key(0x01).fingerprint());
+        assert_ne!(fp.as_ref(), plain.as_ref());
+        assert_eq!(fp.to_string().len(), FINGERPRINT_PREFIX.len() + 52);
+        assert!(fp.to_string().starts_with(FINGERPRINT_PREFIX));
+        assert_eq!(fp.short().len(), 19);
+        assert!(fp.to_string()[FINGERPRINT_PREFIX.len()..].starts_with(&fp.short().replace('-', "")));
+    }
+
+    #[test]
+    fn fingerprint_strings_should_round_trip () {
+        let fp     = key(0x5a).fingerprint();
+
+        assert_eq!(fp.to_string().parse::<Fingerprint>(), Ok(fp));
+        assert_eq!(fp.to_string().to_uppercase().parse::<Fingerprint>(), Ok(fp));
+        assert_eq!(format!(" {} ", fp).parse::<Fingerprint>(), Ok(fp));
+        assert_eq!(base32(b"foobar"), "mzxw6ytboi");
+        assert_eq!(unbase32("mzxw6ytboi"), Some(b"foobar".to_vec()));
+
+        let s      = fp.to_string();
+        // the last character carries one bit of digest and four bits of padding, which must stay zero.
+        let padded = format!("{}{}", &s[..s.len() - 1], if s.ends_with('a') { 'b' } else { 'r' });
+
+        for bad in &[&s[FINGERPRINT_PREFIX.len()..], &s[..s.len() - 1], &format!("{}a", s), &format!("{}0{}", FINGERPRINT_PREFIX, &s[FINGERPRINT_PREFIX.len() + 1..]), &padded, "tc3-sha256:"] {
+            assert_eq!(bad.parse::<Fingerprint>(), Err(TrustError::InvalidKeyEncoding), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn randomart_should_be_framed_and_distinct () {
+        let art    = key(0x00).fingerprint().randomart();
+        let lines  = art.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "+--[ED25519 256]--+");
+        assert_eq!(lines[10], "+--[TC3-SHA256]---+");
+        assert!(lines[1..10].iter().all(|l| l.len() == 19 && l.starts_with('|') && l.ends_with('|')));
+        assert_eq!(lines[1..10].concat().matches('E').count(), 1);
+        assert!(lines[1..10].concat().matches('S').count() <= 1);
+        assert_ne!(art, key(0x01).fingerprint().randomart());
+    }
+
+    #[test]
+    fn store_should_find_keys_by_fingerprint () {
+        let store  = vec!(key(0x01), key(0x02));
+
+        assert_eq!(store.root_key_by_fingerprint(&key(0x02).fingerprint()), Some(key(0x02)));
+        assert_eq!(store.root_key_by_fingerprint(&key(0x03).fingerprint()), None);
+    }
+}
@@ -0,0 +1,221 @@
+use ring::hmac;
+use ring::rand::SystemRandom;
+use crate::v3::PUBLICKEYBYTES;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::fingerprint::Fingerprint;
+use crate::v3::anchor::TrustAnchor;
+use crate::v3::keystore::RootKeysStore;
+use core::borrow::Borrow;
+use core::cmp;
+use core::hash::{Hash, Hasher};
+use std::collections::{BTreeSet, HashSet};
+
+// keys and fingerprints are blinded under separate tags so one can never be looked up as the other.
+const KEY_TAG         : &[u8] = b"trustchain v3 blinded key\x00";
+const FINGERPRINT_TAG : &[u8] = b"trustchain v3 blinded fingerprint\x00";
+
+const BLINDEDBYTES : usize = 32;
+
+//--- A key filed under its HMAC-SHA256, taken with a secret that belongs to the store it sits in
+//
+// Hashing, ordering and comparing only ever look at the blinded bytes, which cannot be predicted
+// without the store's secret, so how long a lookup takes says nothing about the keys that are held.
+#[derive(Copy,Clone,Debug)]
+pub struct BlindedKey {
+    blinded: [u8;BLINDEDBYTES],
+    key: PublicKey,
+}
+
+impl BlindedKey {
+    pub fn key (&self) -> PublicKey { self.key }
+}
+
+impl PartialEq for BlindedKey {
+    fn eq(&self, other: &Self) -> bool { self.blinded == other.blinded }
+}
+
+impl Eq for BlindedKey {}
+
+impl PartialOrd for BlindedKey {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for BlindedKey {
+    fn cmp(&self, other: &Self) -> cmp::Ordering { self.blinded.cmp(&other.blinded) }
+}
+
+impl Hash for BlindedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.blinded.hash(state) }
+}
+
+impl Borrow<[u8;BLINDEDBYTES]> for BlindedKey {
+    fn borrow(&self) -> &[u8;BLINDEDBYTES] { &self.blinded }
+}
+
+//--- The collection an IndexedKeysStore files its blinded keys in
+pub trait KeyIndex: Default {
+    fn insert (&mut self, key: BlindedKey) -> bool;
+    fn remove (&mut self, blinded: &[u8;BLINDEDBYTES]) -> bool;
+    fn get (&self, blinded: &[u8;BLINDEDBYTES]) -> Option<&BlindedKey>;
+    fn len (&self) -> usize;
+    fn is_empty (&self) -> bool { self.len() == 0 }
+    fn keys (&self) -> Box<dyn Iterator<Item = PublicKey> + '_>;
+}
+
+impl KeyIndex for HashSet<BlindedKey> {
+    fn insert (&mut self, key: BlindedKey) -> bool { HashSet::insert(self, key) }
+    fn remove (&mut self, blinded: &[u8;BLINDEDBYTES]) -> bool { HashSet::remove(self, blinded) }
+    fn get (&self, blinded: &[u8;BLINDEDBYTES]) -> Option<&BlindedKey> { HashSet::get(self, blinded) }
+    fn len (&self) -> usize { HashSet::len(self) }
+    fn keys (&self) -> Box<dyn Iterator<Item = PublicKey> + '_> { Box::new(self.iter().map(BlindedKey::key)) }
+}
+
+impl KeyIndex for BTreeSet<BlindedKey> {
+    fn insert (&mut self, key: BlindedKey) -> bool { BTreeSet::insert(self, key) }
+    fn remove (&mut self, blinded: &[u8;BLINDEDBYTES]) -> bool { BTreeSet::remove(self, blinded) }
+    fn get (&self, blinded: &[u8;BLINDEDBYTES]) -> Option<&BlindedKey> { BTreeSet::get(self, blinded) }
+    fn len (&self) -> usize { BTreeSet::len(self) }
+    fn keys (&self) -> Box<dyn Iterator<Item = PublicKey> + '_> { Box::new(self.iter().map(BlindedKey::key)) }
+}
+
+//--- A root keys store for large numbers of roots, with lookups by key or fingerprint that do not scan
+//
+// Keys are blinded with a secret drawn when the store is created, see BlindedKey, so neither the bucket a
+// HashSet probes nor the path a BTreeSet walks depends on how a candidate relates to the keys held.
+pub struct IndexedKeysStore<I> {
+    secret: hmac::Key,
+    keys: I,
+    fingerprints: I,
+}
+
+pub type HashKeysStore  = IndexedKeysStore<HashSet<BlindedKey>>;
+pub type BTreeKeysStore = IndexedKeysStore<BTreeSet<BlindedKey>>;
+
+impl <I: KeyIndex> IndexedKeysStore<I> {
+    pub fn new () -> Result<IndexedKeysStore<I>, TrustError> {
+        let secret = hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new()).map_err(|_| TrustError::KeyGenerationFailed)?;
+        Ok(IndexedKeysStore { secret, keys: I::default(), fingerprints: I::default() })
+    }
+
+    pub fn from_keys <K: IntoIterator<Item = PublicKey>> (keys: K) -> Result<IndexedKeysStore<I>, TrustError> {
+        let mut store = IndexedKeysStore::new()?;
+        for key in keys { store.insert(key); }
+        Ok(store)
+    }
+
+    //--- false when the key was already held
+    pub fn insert (&mut self, key: PublicKey) -> bool {
+        let fingerprint = BlindedKey { blinded: self.blind(FINGERPRINT_TAG, key.fingerprint().as_ref()), key };
+        let inserted    = self.keys.insert(BlindedKey { blinded: self.blind(KEY_TAG, key.as_ref()), key });
+        if inserted { self.fingerprints.insert(fingerprint); }
+        inserted
+    }
+
+    //--- false when the key was not held
+    pub fn remove (&mut self, key: &PublicKey) -> bool {
+        let removed = self.keys.remove(&self.blind(KEY_TAG, key.as_ref()));
+        if removed { self.fingerprints.remove(&self.blind(FINGERPRINT_TAG, key.fingerprint().as_ref())); }
+        removed
+    }
+
+    pub fn contains (&self, key: &PublicKey) -> bool {
+        self.keys.get(&self.blind(KEY_TAG, key.as_ref())).is_some()
+    }
+
+    pub fn len (&self) -> usize { self.keys.len() }
+
+    pub fn is_empty (&self) -> bool { self.keys.is_empty() }
+
+    //--- in no particular order, even for a BTreeSet, since it is ordered by blinded key
+    pub fn keys (&self) -> impl Iterator<Item = PublicKey> + '_ { self.keys.keys() }
+
+    fn blind (&self, tag: &[u8], bytes: &[u8]) -> [u8;BLINDEDBYTES] {
+        let mut ctx = hmac::Context::with_key(&self.secret);
+        ctx.update(tag);
+        ctx.update(bytes);
+        let mut blinded = [0u8;BLINDEDBYTES];
+        blinded.copy_from_slice(ctx.sign().as_ref());
+        blinded
+    }
+}
+
+impl <'ks, I: KeyIndex> RootKeysStore<'ks> for IndexedKeysStore<I> {
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor> {
+        // the length is public, so rejecting a short candidate early gives nothing away.
+        if candidate.len() != PUBLICKEYBYTES { return None }
+        self.keys.get(&self.blind(KEY_TAG, candidate)).map(|k| TrustAnchor::new(k.key))
+    }
+
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.fingerprints.get(&self.blind(FINGERPRINT_TAG, fingerprint.as_ref())).map(BlindedKey::key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::builder::ChainBuilder;
+    use crate::v3::clock::FixedClock;
+    use crate::v3::parsers::trust_chain;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn key (n: u16) -> PublicKey {
+        PublicKey::from_slice(&n.to_be_bytes().repeat(PUBLICKEYBYTES / 2)).unwrap()
+    }
+
+    fn store_should_index_keys <I: KeyIndex> () {
+        let mut store = IndexedKeysStore::<I>::from_keys((0..1_000).map(key)).unwrap();
+
+        assert_eq!(store.len(), 1_000);
+        assert!(store.contains(&key(999)));
+        assert!(store.contains_root_key(key(0).as_ref()));
+        assert!(!store.contains_root_key(key(1_000).as_ref()));
+        assert!(!store.contains_root_key(&key(1).as_ref()[1..]));
+        assert_eq!(store.root_key_by_fingerprint(&key(7).fingerprint()), Some(key(7)));
+        assert_eq!(store.root_key_by_fingerprint(&key(1_000).fingerprint()), None);
+
+        assert!(!store.insert(key(7)));
+        assert!(store.remove(&key(7)));
+        assert!(!store.remove(&key(7)));
+
+        assert_eq!(store.len(), 999);
+        assert!(!store.contains_root_key(key(7).as_ref()));
+        assert_eq!(store.root_key_by_fingerprint(&key(7).fingerprint()), None);
+
+        let mut keys  = store.keys().collect::<Vec<_>>();
+        keys.sort();
+
+        assert_eq!(keys, (0..1_000).filter(|n| *n != 7).map(key).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn hash_store_should_index_keys () {
+        store_should_index_keys::<HashSet<BlindedKey>>();
+    }
+
+    #[test]
+    fn btree_store_should_index_keys () {
+        store_should_index_keys::<BTreeSet<BlindedKey>>();
+    }
+
+    #[test]
+    fn stores_should_blind_under_their_own_secret () {
+        let a          = HashKeysStore::from_keys(vec!(key(1))).unwrap();
+        let b          = HashKeysStore::from_keys(vec!(key(1))).unwrap();
+
+        assert_ne!(a.blind(KEY_TAG, key(1).as_ref()), b.blind(KEY_TAG, key(1).as_ref()));
+        assert_ne!(a.blind(KEY_TAG, key(1).as_ref()), a.blind(FINGERPRINT_TAG, key(1).as_ref()));
+    }
+
+    #[test]
+    fn chains_should_verify_against_an_indexed_store () {
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x20;32]).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let clock          = FixedClock::new(1_500);
+        let root_key_store = BTreeKeysStore::from_keys((0..100).map(key).chain(Some(root))).unwrap();
+        let (chain, _)     = ChainBuilder::new(&root_keypair).intermediate(None).build(None, &root_key_store, &clock).unwrap();
+
+        assert_eq!(trust_chain(&mut chain.as_bytes().as_slice(), &root_key_store, &clock), Ok(chain));
+    }
+}
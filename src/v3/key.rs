@@ -1,6 +1,9 @@
 use crate::v3::PUBLICKEYBYTES;
+use crate::v3::error::TrustError;
 use core::fmt;
 use core::cmp;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
 
 #[derive(Copy,Clone)]
 pub struct PublicKey([u8;PUBLICKEYBYTES]);
@@ -18,11 +21,37 @@ impl PublicKey {
 impl cmp::PartialEq for PublicKey {
     fn eq(&self, other: &Self) -> bool {
         let mut acc = 0;
-        for (l,r) in self.0.iter().zip(other.0.iter()) {acc ^= *l ^ *r;}
+        for (l,r) in self.0.iter().zip(other.0.iter()) {acc |= *l ^ *r;}
         acc == 0
     }
 }
 
+impl cmp::Eq for PublicKey {}
+
+//--- Lexicographic over the key bytes, looking at every byte whatever the outcome
+//
+// Only the single comparison is constant-time: a search through an ordered collection still takes a
+// path that depends on the keys in it, which is why the indexed stores blind keys before ordering them.
+impl cmp::Ord for PublicKey {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        let mut ord = 0i16;
+        for (l,r) in self.0.iter().zip(other.0.iter()) {
+            // all ones until the first differing byte has been seen, zero from then on.
+            let undecided = ((ord == 0) as i16).wrapping_neg();
+            ord |= (*l as i16 - *r as i16) & undecided;
+        }
+        ord.cmp(&0)
+    }
+}
+
+impl cmp::PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> { Some(self.cmp(other)) }
+}
+
+impl Hash for PublicKey {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+
 impl fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let hex_str = self.0.iter().map(|b| format!("{:02x?}",b)).collect::<String>();
@@ -33,3 +62,66 @@ impl fmt::Debug for PublicKey {
 impl AsRef<[u8]> for PublicKey {
     fn as_ref(&self) -> &[u8] { &self.0 }
 }
+
+//--- Lowercase hex, the same digits Debug shows
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for b in self.as_ref() { write!(f, "{:02x}", b)? }
+        Ok(())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = TrustError;
+
+    fn from_str (s: &str) -> Result<PublicKey, TrustError> {
+        let s = s.trim();
+        if !s.is_ascii() || s.len() != PUBLICKEYBYTES * 2 { return Err(TrustError::InvalidKeyEncoding) }
+        let bytes = (0..s.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(TrustError::InvalidKeyEncoding)?;
+        PublicKey::from_slice(&bytes).ok_or(TrustError::InvalidKeyEncoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_differing_in_cancelling_bytes_should_not_be_equal () {
+        let mut other = [0u8;PUBLICKEYBYTES];
+        other[0] = 0x01;
+        other[1] = 0x01;
+
+        assert_ne!(PublicKey([0u8;PUBLICKEYBYTES]), PublicKey(other));
+        assert_eq!(PublicKey(other), PublicKey(other));
+    }
+
+    #[test]
+    fn keys_should_order_like_their_bytes () {
+        let keys   = [[0x00;PUBLICKEYBYTES], [0x01;PUBLICKEYBYTES], [0xff;PUBLICKEYBYTES], [0x7f;PUBLICKEYBYTES]];
+        let mut last_byte = [0x01;PUBLICKEYBYTES];
+        last_byte[PUBLICKEYBYTES - 1] = 0x00;
+
+        for l in keys.iter().chain(Some(&last_byte)) {
+            for r in keys.iter().chain(Some(&last_byte)) {
+                assert_eq!(PublicKey(*l).cmp(&PublicKey(*r)), l.cmp(r));
+            }
+        }
+    }
+
+    #[test]
+    fn keys_should_round_trip_through_hex_strings () {
+        let key    = PublicKey([0x5a;PUBLICKEYBYTES]);
+
+        assert_eq!(key.to_string(), "5a".repeat(PUBLICKEYBYTES));
+        assert_eq!(key.to_string().parse::<PublicKey>(), Ok(key));
+        assert_eq!(format!(" {}\n", key.to_string().to_uppercase()).parse::<PublicKey>(), Ok(key));
+
+        for bad in &[&key.to_string()[1..], &"zz".repeat(PUBLICKEYBYTES), &"é".repeat(PUBLICKEYBYTES)] {
+            assert_eq!(bad.parse::<PublicKey>(), Err(TrustError::InvalidKeyEncoding), "{}", bad);
+        }
+    }
+}
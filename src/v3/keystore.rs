@@ -1,15 +1,49 @@
 use crate::v3::key::PublicKey;
+use crate::v3::fingerprint::Fingerprint;
+use crate::v3::anchor::TrustAnchor;
 
 pub trait RootKeysStore<'ks> {
-    fn contains_root_key <'a> (&'ks self, candidate: &'a [u8]) -> bool;
+    //--- the anchor record for a root key, distrusted anchors included
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor>;
+
+    //--- whether the key is an anchor that has not been distrusted
+    fn contains_root_key <'a> (&'ks self, candidate: &'a [u8]) -> bool {
+        self.trust_anchor(candidate).is_some_and(|a| !a.is_distrusted())
+    }
+
+    //--- the trusted root key with this fingerprint, if there is one
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey>;
+}
+
+//--- The record for a key among several that may repeat it: a distrusted record wins, otherwise the first
+// Distrusting a key is done by adding a record for it, so that record must not be shadowed by an older one.
+pub(crate) fn find_anchor <'a, I> (anchors: I, candidate: &[u8]) -> Option<TrustAnchor>
+where I: Iterator<Item = &'a TrustAnchor> {
+    let mut found: Option<&TrustAnchor> = None;
+    for anchor in anchors.filter(|a| a.key().as_ref() == candidate) {
+        if anchor.is_distrusted() { return Some(anchor.clone()) }
+        found = found.or(Some(anchor));
+    }
+    found.cloned()
 }
 
 impl <'ks> RootKeysStore<'ks> for Vec<PublicKey> {
-    fn contains_root_key <'a> (&'ks self, candidate: &'a [u8]) -> bool {
-        for trusted_key in self.iter() {
-            if  trusted_key.as_ref() == candidate { return true }
-        }
-        false
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor> {
+        self.iter().find(|k| k.as_ref() == candidate).map(|k| TrustAnchor::new(*k))
+    }
+
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.iter().find(|k| k.fingerprint() == *fingerprint).copied()
+    }
+}
+
+impl <'ks> RootKeysStore<'ks> for Vec<TrustAnchor> {
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor> {
+        find_anchor(self.iter(), candidate)
+    }
+
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.iter().map(|a| a.key()).find(|k| k.fingerprint() == *fingerprint)
     }
 }
 
@@ -34,4 +68,30 @@ mod tests {
         assert!(ks.contains_root_key(&bytes))
     }
 
+    #[test]
+    fn anchor_store_should_not_contain_distrusted_keys () {
+        let trusted    = PublicKey::from_slice(&[0x01;PUBLICKEYBYTES]).unwrap();
+        let distrusted = PublicKey::from_slice(&[0x02;PUBLICKEYBYTES]).unwrap();
+        let ks         = vec!(TrustAnchor::new(trusted).with_name("current"), TrustAnchor::new(distrusted).with_distrusted(true));
+
+        assert!(ks.contains_root_key(trusted.as_ref()));
+        assert!(!ks.contains_root_key(distrusted.as_ref()));
+        assert_eq!(ks.trust_anchor(trusted.as_ref()).unwrap().name(), Some("current"));
+        assert!(ks.trust_anchor(distrusted.as_ref()).unwrap().is_distrusted());
+        assert_eq!(ks.trust_anchor(&[0x03;PUBLICKEYBYTES]), None);
+    }
+
+    #[test]
+    fn anchor_store_should_let_a_distrusted_duplicate_win () {
+        let key        = PublicKey::from_slice(&[0x01;PUBLICKEYBYTES]).unwrap();
+        let ks         = vec!(TrustAnchor::new(key).with_name("first"), TrustAnchor::new(key).with_name("second"));
+
+        assert_eq!(ks.trust_anchor(key.as_ref()).unwrap().name(), Some("first"));
+
+        let ks         = vec!(TrustAnchor::new(key), TrustAnchor::new(key).with_distrusted(true));
+
+        assert!(ks.trust_anchor(key.as_ref()).unwrap().is_distrusted());
+        assert!(!ks.contains_root_key(key.as_ref()));
+    }
+
 }
@@ -1,5 +1,40 @@
 use crate::v3::key::PublicKey;
 use crate::v3::signature::Signature;
+use crate::v3::expiry::Expiry;
+use crate::v3::attributes::Attributes;
+use crate::v3::{PUBLICKEYBYTES,EXPIRYBYTES,MAXATTRIBUTEBYTES};
+
+//--- bits of the per-link flags byte in the on-disk format
+pub const LINK_FLAG_EXPIRY     : u8 = 0x01;
+pub const LINK_FLAG_ATTRIBUTES : u8 = 0x02;
+pub const LINK_FLAGS           : u8 = LINK_FLAG_EXPIRY | LINK_FLAG_ATTRIBUTES;
+
+// Leads what a parent signs for a link that carries attributes. Links without attributes sign just the
+// key, and the expiry when present, as they always have, so existing chains keep verifying unchanged.
+const ATTRIBUTES_SIGNATURE_CONTEXT: &[u8] = b"trustchain v3 link attributes\x00";
+
+pub(crate) const MAX_SIGNED_DATA_LEN: usize =
+    ATTRIBUTES_SIGNATURE_CONTEXT.len() + 1 + EXPIRYBYTES + 2 + MAXATTRIBUTEBYTES + PUBLICKEYBYTES;
+
+//--- Assembles what the parent signs for a link into `buf`, returning how much of it was used
+pub(crate) fn write_signed_data (buf: &mut [u8;MAX_SIGNED_DATA_LEN], key: &[u8], expiry: Option<&Expiry>, attributes: &[u8]) -> usize {
+    let mut at = 0;
+    let mut put = |bytes: &[u8]| {
+        buf[at..at + bytes.len()].copy_from_slice(bytes);
+        at += bytes.len();
+    };
+    if !attributes.is_empty() {
+        put(ATTRIBUTES_SIGNATURE_CONTEXT);
+        put(&[if expiry.is_some() { LINK_FLAGS } else { LINK_FLAG_ATTRIBUTES }]);
+    }
+    if let Some(expiry) = expiry { put(&expiry.as_bytes()) }
+    if !attributes.is_empty() {
+        put(&(attributes.len() as u16).to_be_bytes());
+        put(attributes);
+    }
+    put(key);
+    at
+}
 
 #[derive(Copy,Clone,Debug,PartialEq)]
 pub struct Root {
@@ -9,12 +44,42 @@ pub struct Root {
 #[derive(Copy,Clone,Debug,PartialEq)]
 pub struct Link {
     key: PublicKey,
+    expiry: Option<Expiry>,
+    attributes: Attributes,
     sig: Signature,
 }
 
 impl Link {
     pub fn new (key: PublicKey, sig: Signature) -> Link {
-        Link {key, sig}
+        Link {key, expiry: None, attributes: Attributes::new(), sig}
+    }
+
+    pub fn with_expiry (key: PublicKey, expiry: Expiry, sig: Signature) -> Link {
+        Link {key, expiry: Some(expiry), attributes: Attributes::new(), sig}
+    }
+
+    //--- the signature has to cover the attributes too, see signed_data_with_attributes
+    pub fn with_attributes (mut self, attributes: Attributes) -> Link {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn flags (&self) -> u8 {
+        let expiry     = if self.expiry.is_some() { LINK_FLAG_EXPIRY } else { 0 };
+        let attributes = if self.attributes.is_empty() { 0 } else { LINK_FLAG_ATTRIBUTES };
+        expiry | attributes
+    }
+
+    // the bytes covered by the parent's signature: the validity window (when present) followed by the key.
+    pub fn signed_data (key: &PublicKey, expiry: Option<&Expiry>) -> Vec<u8> {
+        Link::signed_data_with_attributes(key, expiry, &Attributes::new())
+    }
+
+    //--- With attributes a context string and the flags byte lead, and the length prefixed block follows the expiry
+    pub fn signed_data_with_attributes (key: &PublicKey, expiry: Option<&Expiry>, attributes: &Attributes) -> Vec<u8> {
+        let mut buf = [0u8;MAX_SIGNED_DATA_LEN];
+        let len = write_signed_data(&mut buf, key.as_ref(), expiry, attributes.as_bytes());
+        buf[..len].to_vec()
     }
 }
 
@@ -35,7 +100,11 @@ pub trait Signed {
 pub trait TrustLink {
     fn key       (&self) -> PublicKey;
     fn signature (&self) -> Option<Signature>;
+    fn expiry    (&self) -> Option<Expiry>;
     fn is_root   (&self) -> bool;
+
+    //--- the signed attributes of the link, empty for roots and for links that carry none
+    fn attributes (&self) -> Attributes { Attributes::new() }
 }
 
 impl Key for Root {
@@ -53,12 +122,14 @@ impl Signed for Link {
 impl TrustLink for Root {
     fn key       (&self) -> PublicKey {self.key}
     fn signature (&self) -> Option<Signature> {None}
+    fn expiry    (&self) -> Option<Expiry> {None}
     fn is_root   (&self) -> bool {true}
 }
 
 impl TrustLink for Link {
     fn key       (&self) -> PublicKey {self.key}
     fn signature (&self) -> Option<Signature> {Some(self.sig)}
+    fn expiry    (&self) -> Option<Expiry> {self.expiry}
     fn is_root   (&self) -> bool {false}
+    fn attributes (&self) -> Attributes {self.attributes}
 }
-
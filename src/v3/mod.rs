@@ -1,24 +1,76 @@
-pub const PUBLICKEYBYTES : usize = 32;
-pub const SIGNATUREBYTES : usize = 64;
+pub const PUBLICKEYBYTES    : usize = 32;
+pub const SIGNATUREBYTES    : usize = 64;
+pub const EXPIRYBYTES       : usize = 16;
+pub const FINGERPRINTBYTES  : usize = 32;
+pub const MAXATTRIBUTEBYTES : usize = 256;
 
 pub const MAXCHAINLINKS  : u8 =  5;
 
 // ------------------------------[   T,   C,  v.    3]
 pub const TC_V3_HEADER: [u8;4] = [0x54,0x43,0x00,0x03];
+// ------------------------------------[   T,   C,  v.  3+f]
+pub const TC_V3_FLAGS_HEADER: [u8;4] = [0x54,0x43,0x01,0x03];
+// ------------------------------[   R,   L,  v.    3]
+pub const RL_V3_HEADER: [u8;4] = [0x52,0x4c,0x00,0x03];
 
 mod error;
 mod key;
+mod fingerprint;
 mod signature;
+mod expiry;
+mod attributes;
+mod clock;
 mod link;
+mod anchor;
 mod keystore;
+mod dir_store;
+mod shared_store;
+mod indexed_store;
+mod revocation;
 mod trust_chain;
+mod trust_chain_ref;
+pub(crate) mod decoder;
 mod parsers;
+#[cfg(feature = "async")]
+mod async_parsers;
+mod signer;
+#[cfg(feature = "keys")]
+mod secret_key;
+#[cfg(unix)]
+mod agent;
+mod builder;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "armor")]
+mod armor;
 
 pub use error::*;
 pub use key::*;
+pub use fingerprint::*;
 pub use link::*;
 pub use signature::*;
+pub use expiry::*;
+pub use attributes::*;
+pub use clock::*;
+pub use anchor::*;
 pub use keystore::*;
+pub use dir_store::*;
+pub use shared_store::*;
+pub use indexed_store::*;
+pub use revocation::*;
 pub use trust_chain::*;
+pub use trust_chain_ref::*;
 pub use parsers::*;
+#[cfg(feature = "async")]
+pub use async_parsers::*;
+pub use signer::*;
+#[cfg(feature = "keys")]
+pub use secret_key::*;
+#[cfg(unix)]
+pub use agent::*;
+pub use builder::*;
+#[cfg(feature = "serde")]
+pub use serialization::*;
+#[cfg(feature = "armor")]
+pub use armor::*;
 
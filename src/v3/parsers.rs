@@ -1,51 +1,96 @@
-use crate::trust_chain_v3;
-use crate::v3::trust_chain::{TrustChain};
-use crate::v3::link::{Root, Link};
-use crate::v3::{PUBLICKEYBYTES,MAXCHAINLINKS,TC_V3_HEADER};
+use crate::v3::trust_chain::TrustChain;
+use crate::v3::link::{Root, TrustLink};
+use crate::v3::{PUBLICKEYBYTES,SIGNATUREBYTES,RL_V3_HEADER};
 use crate::v3::error::TrustError;
 use crate::v3::key::PublicKey;
 use crate::v3::signature::Signature;
 use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::revocation::RevocationList;
+use crate::v3::trust_chain_ref::{TrustChainRef, LinkRef, verify_link};
+use crate::v3::decoder::{Decoded, link_layout, decode_link, read_field, read_link_with};
 
-use std::io::Read;
-
-#[inline]
-fn read_link <R> (input: &mut R) -> Result<Link, TrustError>
-where R: Read  {
-    let mut buf = [0u8;96];
-    let bytes_read = input.read(&mut buf).map_err(|_| TrustError::InvalidTrustChain)?;
-    if bytes_read != 96 { return Err(TrustError::InvalidTrustChain) }
-    PublicKey::from_slice(&buf[0..PUBLICKEYBYTES])
-        .and_then(|key| Signature::from_slice(&buf[PUBLICKEYBYTES..96]).map(|sig| Link::new(key,sig)))
-        .ok_or(TrustError::InvalidTrustChain)
-}
+use std::io::{self, Read};
 
 #[inline]
 fn read_root <R> (input: &mut R) -> Result<Root, TrustError>
 where R: Read  {
     let mut buf = [0u8;PUBLICKEYBYTES];
-    let bytes_read = input.read(&mut buf).map_err(|_| TrustError::InvalidTrustChain)?;
-    if bytes_read != PUBLICKEYBYTES { return Err(TrustError::InvalidTrustChain) }
+    read_field(input, &mut buf)?;
     PublicKey::from_slice(&buf)
-        .map(|key| Root::new(key))
+        .map(Root::new)
         .ok_or(TrustError::InvalidTrustChain)
 }
 
-pub fn trust_chain <'ks, R> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks)) -> Result<TrustChain,TrustError>
+pub fn trust_chain <'ks, R> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain,TrustError>
+where R: Read  {
+    trust_chain_bounded(input, root_keys_store, clock)
+}
+
+//--- As trust_chain, for verifiers that take chains of up to N links rather than MAXCHAINLINKS
+pub fn trust_chain_bounded <'ks, R, const N: usize> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain<N>,TrustError>
 where R: Read  {
     let mut buf = [0u8;5];
-    let bytes_read = input.read(&mut buf).map_err(|_| TrustError::InvalidTrustChain)?;
+    read_field(input, &mut buf)?;
 
-    if bytes_read != 5 {
-        return Err(TrustError::InvalidTrustChain)
+    let layout = link_layout(&buf[0..4])?;
+
+    let chain_length = buf[4] ;
+    if chain_length as usize > N {
+        return Err(TrustError::MaxChainLengthExceeded)
     }
 
-    if buf[0..4] != TC_V3_HEADER {
+    if chain_length < 1 {
         return Err(TrustError::InvalidTrustChain)
     }
 
-    let chain_length = buf[4] ;
-    if chain_length > MAXCHAINLINKS {
+    let mut chain = TrustChain::bounded(root_keys_store, read_root(input)?, clock)?;
+    for _ in 1..chain_length {
+        chain = chain.append(read_link_with(input, layout, |link| link.to_link())?, clock)?;
+    }
+    Ok(chain)
+}
+
+//--- As trust_chain, but the input must end with the last link, so only use it on inputs that do end
+pub fn trust_chain_strict <'ks, R> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain,TrustError>
+where R: Read  {
+    let chain = trust_chain(input, root_keys_store, clock)?;
+    let mut buf = [0u8;1];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0)  => return Ok(chain),
+            Ok(_)  => return Err(TrustError::TrailingData),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(TrustError::ReadFailed(e.kind())),
+        }
+    }
+}
+
+#[inline]
+fn take <'a> (input: &mut &'a [u8], n: usize) -> Result<&'a [u8], TrustError> {
+    if input.len() < n { return Err(TrustError::UnexpectedEof) }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Ok(head)
+}
+
+//--- Zero-copy counterpart of trust_chain, checking the same things in place over the input slice
+pub fn trust_chain_ref <'a, 'ks> (input: &'a [u8], root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChainRef<'a>,TrustError> {
+    trust_chain_ref_bounded(input, root_keys_store, clock)
+}
+
+//--- As trust_chain_ref, for verifiers that take chains of up to N links rather than MAXCHAINLINKS
+pub fn trust_chain_ref_bounded <'a, 'ks, const N: usize> (input: &'a [u8], root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChainRef<'a, N>,TrustError> {
+    // the same capacity check as TrustChain::bounded, the links array below needs at least the root's slot.
+    #[allow(clippy::let_unit_value)]
+    let _ = TrustChain::<N>::CAPACITY;
+    let mut rest = input;
+    let header = take(&mut rest, 5)?;
+
+    let layout = link_layout(&header[0..4])?;
+
+    let chain_length = header[4];
+    if chain_length as usize > N {
         return Err(TrustError::MaxChainLengthExceeded)
     }
 
@@ -53,19 +98,83 @@ where R: Read  {
         return Err(TrustError::InvalidTrustChain)
     }
 
-    match chain_length {
-        1 => { trust_chain_v3!(root_keys_store, read_root(input)?) },
-        2 => { trust_chain_v3!(root_keys_store, read_root(input)?, read_link(input)?) },
-        3 => { trust_chain_v3!(root_keys_store, read_root(input)?, read_link(input)?, read_link(input)?) },
-        4 => { trust_chain_v3!(root_keys_store, read_root(input)?, read_link(input)?, read_link(input)?, read_link(input)?) },
-        5 => { trust_chain_v3!(root_keys_store, read_root(input)?, read_link(input)?, read_link(input)?, read_link(input)?, read_link(input)?) },
-        _ => Err(TrustError::InvalidTrustChain)
+    let root   = take(&mut rest, PUBLICKEYBYTES)?;
+    let anchor = root_keys_store.trust_anchor(root).ok_or(TrustError::NoRootKeyTrust)?;
+    anchor.admits_at(clock.now())?;
+
+    let mut links = [LinkRef { key: root, expiry: None, attributes: &[], sig: None }; N];
+    let mut depth_limit = usize::MAX;
+    for i in 1..chain_length as usize {
+        let encoded = match decode_link(rest, layout)? {
+            Decoded::Link(link) => link,
+            Decoded::Needs(_)   => return Err(TrustError::UnexpectedEof),
+        };
+        rest = &rest[encoded.len..];
+
+        let (expiry, parsed) = (encoded.expiry, encoded.parsed);
+        let link = LinkRef { key: encoded.key, expiry, attributes: encoded.attributes, sig: Some(encoded.sig) };
+        verify_link(links[i - 1].key, &link)?;
+
+        if !links[i - 1].attributes().permits_link_signing() { return Err(TrustError::LinkSigningNotPermitted) }
+
+        if let Some(expiry) = expiry {
+            if !expiry.is_valid_at(clock.now()) { return Err(TrustError::InvalidExpiry) }
+        }
+        anchor.allows_depth(i)?;
+        if i > depth_limit { return Err(TrustError::PathLengthExceeded) }
+        if let Some(max) = parsed.max_depth() { depth_limit = depth_limit.min(i + max as usize) }
+        links[i] = link;
+    }
+
+    Ok(TrustChainRef { anchor, bytes: &input[..input.len() - rest.len()], len: chain_length, links })
+}
+
+//--- As trust_chain_ref, but the slice must hold nothing past the last link
+pub fn trust_chain_ref_strict <'a, 'ks> (input: &'a [u8], root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChainRef<'a>,TrustError> {
+    let chain = trust_chain_ref(input, root_keys_store, clock)?;
+    if chain.as_bytes().len() != input.len() { return Err(TrustError::TrailingData) }
+    Ok(chain)
+}
+
+pub fn revocation_list <R> (input: &mut R) -> Result<RevocationList,TrustError>
+where R: Read  {
+    let mut buf = [0u8;4 + PUBLICKEYBYTES + 2];
+    read_field(input, &mut buf)?;
+
+    if buf[0..4] != RL_V3_HEADER {
+        return Err(TrustError::InvalidRevocationList)
+    }
+
+    let issuer = PublicKey::from_slice(&buf[4..4 + PUBLICKEYBYTES]).ok_or(TrustError::InvalidRevocationList)?;
+    let count  = u16::from_be_bytes([buf[4 + PUBLICKEYBYTES], buf[5 + PUBLICKEYBYTES]]);
+
+    // the count is untrusted, so only the keys that actually arrive are buffered and reserved for.
+    let mut keys = Vec::new();
+    (&mut *input).take(u64::from(count) * PUBLICKEYBYTES as u64).read_to_end(&mut keys)
+        .map_err(|e| TrustError::ReadFailed(e.kind()))?;
+    if keys.len() != usize::from(count) * PUBLICKEYBYTES { return Err(TrustError::UnexpectedEof) }
+
+    let mut rest = keys.as_slice();
+    let mut revoked = Vec::with_capacity(rest.len() / PUBLICKEYBYTES);
+    while !rest.is_empty() {
+        revoked.push(PublicKey::from_slice(take(&mut rest, PUBLICKEYBYTES)?).ok_or(TrustError::InvalidRevocationList)?);
     }
+
+    let mut buf = [0u8;SIGNATUREBYTES];
+    read_field(input, &mut buf)?;
+    let sig = Signature::from_slice(&buf).ok_or(TrustError::InvalidRevocationList)?;
+
+    RevocationList::new(issuer, revoked, sig)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::v3::{PUBLICKEYBYTES,SIGNATUREBYTES};
+    use crate::trust_chain_v3;
+    use crate::v3::expiry::{NotBefore, NotAfter};
+    use crate::v3::clock::{SystemClock, FixedClock};
+    use crate::v3::{PUBLICKEYBYTES,SIGNATUREBYTES,MAXCHAINLINKS,TC_V3_HEADER,TC_V3_FLAGS_HEADER};
+    use crate::v3::link::{Link, LINK_FLAG_EXPIRY, LINK_FLAG_ATTRIBUTES};
+    use crate::v3::expiry::Expiry;
     use ring::rand::{SystemRandom, SecureRandom};
     use ring::signature;
     use ring::signature::KeyPair;
@@ -80,7 +189,7 @@ mod tests {
         let mut i = 0;
         while i < count {
             assert_eq!(lft[i+lft_off], rgt[i+rgt_off]);
-            i = i + 1;
+            i += 1;
         }
     }
 
@@ -94,8 +203,8 @@ mod tests {
 
     macro_rules! assert_ok {
         ($res:expr) => {{
-            if $res.is_err() {
-                assert!(false, format!("{:?}",$res.unwrap_err()));
+            if let Err(e) = &$res {
+                panic!("{:?}", e);
             }
         }};
     }
@@ -110,14 +219,14 @@ mod tests {
 
         let root           = pkey.unwrap();
         let root_key_store = vec!(root);
-        let chain          = TrustChain::new(&root_key_store, Root::new(root));
+        let chain          = TrustChain::new(&root_key_store, Root::new(root), &SystemClock);
 
         assert_ok!(chain);
 
         let chain          = chain.unwrap();
         let test_data      = b"test data";
         let sig            = Signature::from(keypair.sign(test_data));
-        let verify_res     = chain.verify_data(&sig, test_data);
+        let verify_res     = chain.verify_data(&sig, test_data, &SystemClock);
 
         assert_ok!(verify_res);
 
@@ -127,12 +236,12 @@ mod tests {
         assert_eq!(cb[4], 0x01);// chain length
         assert_key(&cb,5, root.as_ref(), 0);// root public key, 32 bytes
 
-        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store);
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &SystemClock);
 
         assert_ok!(parsed_chain);
 
         let parsed_chain   = parsed_chain.unwrap();
-        let verify_parsed  = parsed_chain.verify_data(&sig, test_data);
+        let verify_parsed  = parsed_chain.verify_data(&sig, test_data, &SystemClock);
 
         assert_ok!(verify_parsed);
     }
@@ -157,14 +266,14 @@ mod tests {
 
         let root_sig       = root_sig.unwrap();
         let link1          = Link::new(end, root_sig);
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1);
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1);
 
         assert_ok!(chain);
 
         let chain          = chain.unwrap();
         let test_data      = b"test data";
         let end_sig        = Signature::from(end_keypair.sign(test_data));
-        let verify_res     = chain.verify_data(&end_sig, test_data);
+        let verify_res     = chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_res);
 
@@ -176,12 +285,12 @@ mod tests {
         assert_key(&cb,37, end.as_ref(),  0);
         assert_sig(&cb,69, root_sig.as_ref(),  0);
 
-        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store);
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &SystemClock);
 
         assert_ok!(parsed_chain);
 
         let parsed_chain   = parsed_chain.unwrap();
-        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data);
+        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_parsed);
     }
@@ -214,14 +323,14 @@ mod tests {
         let intr_sig       = intr_sig.unwrap();
         let link1          = Link::new(intr, root_sig);
         let link2          = Link::new(end,  intr_sig);
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1, link2);
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1, link2);
 
         assert_ok!(chain);
 
         let chain          = chain.unwrap();
         let test_data      = b"test data";
         let end_sig        = Signature::from(end_keypair.sign(test_data));
-        let verify_res     = chain.verify_data(&end_sig, test_data);
+        let verify_res     = chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_res);
 
@@ -235,12 +344,12 @@ mod tests {
         assert_key(&cb,133, end.as_ref(),0);
         assert_sig(&cb,165, intr_sig.as_ref(),0);
 
-        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store);
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &SystemClock);
 
         assert_ok!(parsed_chain);
 
         let parsed_chain   = parsed_chain.unwrap();
-        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data);
+        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_parsed);
     }
@@ -281,14 +390,14 @@ mod tests {
         let link1          = Link::new(intr1, root_sig);
         let link2          = Link::new(intr2, intr1_sig);
         let link3          = Link::new(end,   intr2_sig);
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1, link2, link3);
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1, link2, link3);
 
         assert_ok!(chain);
 
         let chain          = chain.unwrap();
         let test_data      = b"test data";
         let end_sig        = Signature::from(end_keypair.sign(test_data));
-        let verify_res     = chain.verify_data(&end_sig, test_data);
+        let verify_res     = chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_res);
 
@@ -304,12 +413,12 @@ mod tests {
         assert_key(&cb,229, end.as_ref(),0);
         assert_sig(&cb,261, intr2_sig.as_ref(),0);
 
-        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store);
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &SystemClock);
 
         assert_ok!(parsed_chain);
 
         let parsed_chain   = parsed_chain.unwrap();
-        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data);
+        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_parsed);
     }
@@ -358,14 +467,14 @@ mod tests {
         let link2          = Link::new(intr2, intr1_sig);
         let link3          = Link::new(intr3, intr2_sig);
         let link4          = Link::new(end,   intr3_sig);
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1, link2, link3, link4);
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1, link2, link3, link4);
 
         assert_ok!(chain);
 
         let chain          = chain.unwrap();
         let test_data      = b"test data";
         let end_sig        = Signature::from(end_keypair.sign(test_data));
-        let verify_res     = chain.verify_data(&end_sig, test_data);
+        let verify_res     = chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_res);
 
@@ -383,13 +492,424 @@ mod tests {
         assert_key(&cb,325, end.as_ref(),0);
         assert_sig(&cb,357, intr3_sig.as_ref(),0);
 
-        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store);
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &SystemClock);
 
         assert_ok!(parsed_chain);
 
         let parsed_chain   = parsed_chain.unwrap();
-        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data);
+        let verify_parsed  = parsed_chain.verify_data(&end_sig, test_data, &SystemClock);
 
         assert_ok!(verify_parsed);
     }
+
+    #[test]
+    fn expiring_link_chain_end_to_end_test () {
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let end_keypair    = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let signed_data    = Link::signed_data(&end, Some(&expiry));
+        let root_sig       = Signature::from(root_keypair.sign(&signed_data));
+        let link1          = Link::with_expiry(end, expiry, root_sig);
+        let chain          = TrustChain::new(&root_key_store, Root::new(root), &FixedClock::new(1_500))
+            .and_then(|chain| chain.append(link1, &FixedClock::new(1_500)));
+
+        assert_ok!(chain);
+
+        let cb             = chain.unwrap().as_bytes();
+
+        assert_eq!(cb[0..4], TC_V3_FLAGS_HEADER);
+        assert_eq!(cb[4], 0x02);// chain length
+        assert_key(&cb,37, end.as_ref(), 0);
+        assert_eq!(cb[69], LINK_FLAG_EXPIRY);
+        assert_eq!(cb[70..86], expiry.as_bytes());
+        assert_sig(&cb,86, root_sig.as_ref(), 0);
+
+        let parsed_chain   = trust_chain(&mut cb.as_slice(), &root_key_store, &FixedClock::new(1_500));
+
+        assert_ok!(parsed_chain);
+        assert_eq!(parsed_chain.unwrap().last().expiry(), Some(expiry));
+
+        let expired_chain  = trust_chain(&mut cb.as_slice(), &root_key_store, &FixedClock::new(2_001));
+
+        assert_eq!(expired_chain, Err(TrustError::InvalidExpiry));
+
+        let early_chain    = trust_chain(&mut cb.as_slice(), &root_key_store, &FixedClock::new(999));
+
+        assert_eq!(early_chain, Err(TrustError::InvalidExpiry));
+
+        let mut stripped   = cb.clone();
+        stripped[0..4].copy_from_slice(&TC_V3_HEADER);
+        stripped.drain(69..86);
+
+        assert_eq!(trust_chain(&mut stripped.as_slice(), &root_key_store, &FixedClock::new(1_500)), Err(TrustError::InvalidSignature));
+
+        let mut unknown    = cb.clone();
+        unknown[69]        = 0x80 | LINK_FLAG_EXPIRY;
+
+        assert_eq!(trust_chain(&mut unknown.as_slice(), &root_key_store, &FixedClock::new(1_500)), Err(TrustError::InvalidTrustChain));
+    }
+
+    #[test]
+    fn borrowed_chain_should_match_owned_parse () {
+        use crate::v3::builder::ChainBuilder;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let clock          = FixedClock::new(1_500);
+
+        for intermediates in 0..4 {
+            let mut builder = ChainBuilder::new(&root_keypair);
+            for i in 0..intermediates {
+                builder = builder.intermediate(if i % 2 == 0 { Some(expiry) } else { None });
+            }
+            let (chain, end) = builder.build(Some(expiry), &root_key_store, &clock).unwrap();
+            let mut cb       = chain.as_bytes();
+            let borrowed     = trust_chain_ref(&cb, &root_key_store, &clock).unwrap();
+
+            assert_eq!(borrowed.len(), chain.len());
+            assert_eq!(borrowed.as_bytes(), cb.as_slice());
+            assert_eq!(borrowed.first().key(), root);
+            assert_eq!(borrowed.last().key(), chain.last().key());
+            for (b, o) in borrowed.iter().zip(chain.iter()) {
+                assert_eq!(b.key(), o.key());
+                assert_eq!(b.signature(), o.signature());
+                assert_eq!(b.expiry(), o.expiry());
+                assert_eq!(b.is_root(), o.is_root());
+            }
+
+            let sig          = Signature::from(end.sign(b"test data"));
+
+            assert_ok!(borrowed.verify_data(&sig, b"test data", &clock));
+            assert_eq!(borrowed.verify_data(&sig, b"tset data", &clock), Err(TrustError::InvalidSignature));
+            assert_eq!(borrowed.verify_data(&sig, b"test data", &FixedClock::new(2_001)), Err(TrustError::InvalidExpiry));
+
+            // whatever follows the chain is left alone rather than folded into it.
+            let chain_len    = cb.len();
+            cb.extend_from_slice(b"trailing");
+
+            assert_eq!(trust_chain_ref(&cb, &root_key_store, &clock).map(|c| c.as_bytes().len()), Ok(chain_len));
+        }
+    }
+
+    #[test]
+    fn borrowed_chain_should_reject_what_owned_parse_rejects () {
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let end_keypair    = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let root_sig       = Signature::from(root_keypair.sign(&Link::signed_data(&end, Some(&expiry))));
+        let clock          = FixedClock::new(1_500);
+        let expired        = FixedClock::new(2_001);
+        let cb             = trust_chain_v3!(&root_key_store, &clock, Root::new(root), Link::with_expiry(end, expiry, root_sig))
+            .unwrap()
+            .as_bytes();
+
+        let mut bad_sig    = cb.clone();
+        bad_sig[100]      ^= 0x01;
+        let mut bad_flags  = cb.clone();
+        bad_flags[69]      = 0x80 | LINK_FLAG_EXPIRY;
+        let mut too_long   = cb.clone();
+        too_long[4]        = MAXCHAINLINKS + 1;
+        let mut empty      = cb.clone();
+        empty[4]           = 0x00;
+
+        let cases: Vec<(&[u8], &FixedClock)> = vec!(
+            (&cb[..cb.len() - 1], &clock),
+            (&cb[..3],            &clock),
+            (&bad_sig,            &clock),
+            (&bad_flags,          &clock),
+            (&too_long,           &clock),
+            (&empty,              &clock),
+            (&cb,                 &expired),
+        );
+
+        for (input, clock) in cases {
+            let owned      = trust_chain(&mut &input[..], &root_key_store, clock).map(|_| ());
+            let borrowed   = trust_chain_ref(input, &root_key_store, clock).map(|_| ());
+
+            assert!(owned.is_err());
+            assert_eq!(borrowed, owned);
+        }
+
+        assert_eq!(trust_chain_ref(&cb, &vec!(end), &clock).map(|_| ()), Err(TrustError::NoRootKeyTrust));
+    }
+
+    #[test]
+    fn parsers_should_enforce_the_anchor_terms () {
+        use crate::v3::anchor::TrustAnchor;
+        use crate::v3::builder::ChainBuilder;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let clock          = FixedClock::new(1_500);
+        let anchor         = TrustAnchor::new(root)
+            .with_name("prod root")
+            .with_validity(Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap())
+            .with_max_depth(2);
+        let (chain, _)     = ChainBuilder::new(&root_keypair).build(None, &vec!(root), &clock).unwrap();
+        let (deep, _)      = ChainBuilder::new(&root_keypair).intermediate(None).intermediate(None).build(None, &vec!(root), &clock).unwrap();
+        let (cb, db)       = (chain.as_bytes(), deep.as_bytes());
+
+        let parsed         = trust_chain(&mut cb.as_slice(), &vec!(anchor.clone()), &clock).unwrap();
+
+        assert_eq!(parsed.anchor().name(), Some("prod root"));
+        assert_eq!(trust_chain_ref(&cb, &vec!(anchor.clone()), &clock).map(|c| c.anchor().name().map(String::from)), Ok(Some("prod root".to_string())));
+
+        let cases = vec!(
+            (&cb, anchor.clone(), FixedClock::new(2_001), TrustError::InvalidExpiry),
+            (&cb, anchor.clone().with_distrusted(true), clock, TrustError::DistrustedAnchor),
+            (&db, anchor.clone(), clock, TrustError::PathLengthExceeded),
+        );
+
+        for (input, anchor, clock, error) in cases {
+            let store      = vec!(anchor);
+
+            assert_eq!(trust_chain(&mut input.as_slice(), &store, &clock).map(|_| ()), Err(error));
+            assert_eq!(trust_chain_ref(input, &store, &clock).map(|_| ()), Err(error));
+        }
+
+        // the anchor's validity also bounds verification later on, not just parsing.
+        assert_eq!(parsed.is_valid_at(2_001), Err(TrustError::InvalidExpiry));
+    }
+
+    #[test]
+    fn attributed_links_should_parse_and_stay_signed () {
+        use crate::v3::attributes::{Attributes, KeyUsage};
+        use crate::v3::builder::{issue_link, issue_link_with_attributes};
+        use crate::v3::link::TrustLink;
+        use crate::v3::trust_chain::TrustChain;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let intr_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let intr           = PublicKey::from_slice(intr_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(gen_keypair(&rand).unwrap().public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let clock          = FixedClock::new(1_500);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let intr_attrs     = Attributes::new().with_key_usage(KeyUsage::SIGN_LINKS).unwrap().with_label("issuing ca").unwrap();
+        let end_attrs      = Attributes::new().with_extension(0x41, true, b"tenant-7").unwrap();
+        let chain          = TrustChain::new(&root_key_store, Root::new(root), &clock).unwrap()
+            .append(issue_link_with_attributes(&root_keypair, intr, Some(expiry), intr_attrs).unwrap(), &clock).unwrap()
+            .append(issue_link_with_attributes(&intr_keypair, end, None, end_attrs).unwrap(), &clock).unwrap();
+        let cb             = chain.as_bytes();
+
+        // key, flags, expiry, then the length prefixed block.
+        assert_eq!(cb[69], LINK_FLAG_EXPIRY | LINK_FLAG_ATTRIBUTES);
+        assert_eq!(&cb[86..88], &(intr_attrs.as_bytes().len() as u16).to_be_bytes());
+        assert_eq!(cb.len(), chain.encoded_len());
+
+        let owned          = trust_chain(&mut cb.as_slice(), &root_key_store, &clock).unwrap();
+        let borrowed       = trust_chain_ref(&cb, &root_key_store, &clock).unwrap();
+
+        assert_eq!(owned, chain);
+        assert_eq!(owned.iter().map(|l| l.attributes()).collect::<Vec<_>>(), vec!(Attributes::new(), intr_attrs, end_attrs));
+        assert_eq!(borrowed.iter().map(|l| l.attributes()).collect::<Vec<_>>(), vec!(Attributes::new(), intr_attrs, end_attrs));
+        assert_eq!(owned.iter().nth(1).and_then(|l| l.attributes().label().map(String::from)), Some("issuing ca".to_string()));
+        assert_eq!(owned.verify_critical_attributes(&[]), Err(TrustError::UnknownCriticalAttribute(0x41)));
+        assert_eq!(owned.verify_critical_attributes(&[0x41]), Ok(()));
+        assert_eq!(borrowed.verify_critical_attributes(&[]), Err(TrustError::UnknownCriticalAttribute(0x41)));
+
+        // the block is signed, and a bad length never gets as far as the signature.
+        let mut relabelled = cb.clone();
+        relabelled[93]    ^= 0x01;
+        let mut empty      = cb.clone();
+        empty[86..88].copy_from_slice(&[0x00, 0x00]);
+        let mut oversized  = cb.clone();
+        oversized[86..88].copy_from_slice(&[0x01, 0x01]);
+        let mut reserved   = cb.clone();
+        reserved[69]      |= 0x04;
+
+        let cases = vec!(
+            (relabelled, TrustError::InvalidSignature),
+            (empty,      TrustError::InvalidAttributes),
+            (oversized,  TrustError::InvalidAttributes),
+            (reserved,   TrustError::InvalidTrustChain),
+        );
+
+        for (input, error) in cases {
+            assert_eq!(trust_chain(&mut input.as_slice(), &root_key_store, &clock).map(|_| ()), Err(error));
+            assert_eq!(trust_chain_ref(&input, &root_key_store, &clock).map(|_| ()), Err(error));
+        }
+
+        // links without attributes keep signing exactly what they always did.
+        let legacy         = issue_link(&root_keypair, end, None).unwrap();
+
+        assert_eq!(Link::signed_data(&end, None), end.as_ref().to_vec());
+        assert_eq!(legacy.attributes(), Attributes::new());
+        assert_eq!(TrustChain::new(&root_key_store, Root::new(root), &clock).unwrap().append(legacy, &clock).unwrap().as_bytes()[0..4], TC_V3_HEADER);
+    }
+
+    #[test]
+    fn parsers_should_refuse_links_beneath_data_only_keys () {
+        use crate::v3::builder::ChainBuilder;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let extra          = PublicKey::from_slice(gen_keypair(&rand).unwrap().public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let clock          = FixedClock::new(1_500);
+        let (chain, end)   = ChainBuilder::new(&root_keypair).intermediate(None).build(None, &root_key_store, &clock).unwrap();
+
+        // append can't produce this chain, so the extra link is encoded by hand.
+        let mut cb         = chain.as_bytes();
+        cb[4]             += 1;
+        cb.extend_from_slice(extra.as_ref());
+        cb.push(0x00);
+        cb.extend_from_slice(end.sign(extra.as_ref()).as_ref());
+
+        assert_eq!(trust_chain(&mut cb.as_slice(), &root_key_store, &clock).map(|_| ()), Err(TrustError::LinkSigningNotPermitted));
+        assert_eq!(trust_chain_ref(&cb, &root_key_store, &clock).map(|_| ()), Err(TrustError::LinkSigningNotPermitted));
+
+        // signing data instead is what the end key was issued for.
+        assert_ok!(trust_chain_ref(&chain.as_bytes(), &root_key_store, &clock).unwrap().verify_data(&Signature::from(end.sign(b"test data")), b"test data", &clock));
+    }
+
+    #[test]
+    fn borrowed_chain_should_refuse_data_from_link_only_keys () {
+        use crate::v3::attributes::{Attributes, KeyUsage};
+        use crate::v3::builder::issue_link_with_attributes;
+        use crate::v3::trust_chain::TrustChain;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let intr_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let intr           = PublicKey::from_slice(intr_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let clock          = FixedClock::new(1_500);
+        let usage          = Attributes::new().with_key_usage(KeyUsage::SIGN_LINKS).unwrap();
+        let cb             = TrustChain::new(&root_key_store, Root::new(root), &clock).unwrap()
+            .append(issue_link_with_attributes(&root_keypair, intr, None, usage).unwrap(), &clock).unwrap()
+            .as_bytes();
+        let borrowed       = trust_chain_ref(&cb, &root_key_store, &clock).unwrap();
+        let sig            = Signature::from(intr_keypair.sign(b"test data"));
+
+        assert_eq!(borrowed.verify_data(&sig, b"test data", &clock), Err(TrustError::DataSigningNotPermitted));
+    }
+
+    #[test]
+    fn parsers_should_enforce_link_max_depth () {
+        use crate::v3::attributes::Attributes;
+
+        let rand           = SystemRandom::new();
+        let keypairs       = (0..4).map(|_| gen_keypair(&rand).unwrap()).collect::<Vec<_>>();
+        let keys           = keypairs.iter().map(|k| PublicKey::from_slice(k.public_key().as_ref()).unwrap()).collect::<Vec<_>>();
+        let root_key_store = vec!(keys[0]);
+        let clock          = FixedClock::new(1_500);
+        let team           = Attributes::new().with_max_depth(1).unwrap();
+        let team_sig       = Signature::from(keypairs[0].sign(&Link::signed_data_with_attributes(&keys[1], None, &team)));
+        let cb             = trust_chain_v3!(&root_key_store, &clock, Root::new(keys[0]), Link::new(keys[1], team_sig).with_attributes(team))
+            .unwrap()
+            .as_bytes();
+
+        // append refuses to go deeper, so the links past the team key are encoded by hand.
+        let mut deep       = cb.clone();
+        deep[4]           += 2;
+        for at in 2..4 {
+            deep.extend_from_slice(keys[at].as_ref());
+            deep.push(0x00);
+            deep.extend_from_slice(keypairs[at - 1].sign(keys[at].as_ref()).as_ref());
+        }
+        let mut end        = deep[..deep.len() - (PUBLICKEYBYTES + 1 + SIGNATUREBYTES)].to_vec();
+        end[4]            -= 1;
+
+        assert_eq!(trust_chain(&mut end.as_slice(), &root_key_store, &clock).map(|c| c.len()), Ok(3));
+        assert_eq!(trust_chain_ref(&end, &root_key_store, &clock).map(|c| c.len()), Ok(3));
+        assert_eq!(trust_chain(&mut deep.as_slice(), &root_key_store, &clock).map(|_| ()), Err(TrustError::PathLengthExceeded));
+        assert_eq!(trust_chain_ref(&deep, &root_key_store, &clock).map(|_| ()), Err(TrustError::PathLengthExceeded));
+    }
+
+    // hands out at most `step` bytes per read, and reports an interruption before every other read.
+    struct Dribble<'a> { input: &'a [u8], step: usize, interrupt: bool }
+
+    impl <'a> Read for Dribble<'a> {
+        fn read (&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt { return Err(io::Error::from(io::ErrorKind::Interrupted)) }
+            let n = self.step.min(buf.len()).min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn chain_split_across_short_reads_should_parse () {
+        use crate::v3::builder::ChainBuilder;
+
+        let root_keypair   = gen_keypair(&SystemRandom::new()).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let clock          = FixedClock::new(1_500);
+        let (chain, _)     = ChainBuilder::new(&root_keypair)
+            .intermediate(Some(expiry))
+            .intermediate(None)
+            .build(Some(expiry), &root_key_store, &clock)
+            .unwrap();
+        let cb             = chain.as_bytes();
+
+        for step in [1, 3, 31, 64].iter() {
+            let mut input  = Dribble { input: &cb, step: *step, interrupt: false };
+
+            assert_eq!(trust_chain_strict(&mut input, &root_key_store, &clock).as_ref(), Ok(&chain));
+        }
+
+        let mut buffered   = std::io::BufReader::with_capacity(10, cb.as_slice());
+
+        assert_eq!(trust_chain(&mut buffered, &root_key_store, &clock), Ok(chain));
+    }
+
+    #[test]
+    fn truncation_trailing_data_and_read_failures_should_be_told_apart () {
+        let root_keypair   = gen_keypair(&SystemRandom::new()).unwrap();
+        let end_keypair    = gen_keypair(&SystemRandom::new()).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let root_sig       = Signature::from(root_keypair.sign(end.as_ref()));
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), Link::new(end, root_sig)).unwrap();
+        let cb             = chain.as_bytes();
+
+        for cut in [0, 3, 5, 36, 37, 70, cb.len() - 1].iter() {
+            assert_eq!(trust_chain(&mut &cb[..*cut], &root_key_store, &SystemClock), Err(TrustError::UnexpectedEof));
+            assert_eq!(trust_chain_ref(&cb[..*cut], &root_key_store, &SystemClock).map(|_| ()), Err(TrustError::UnexpectedEof));
+        }
+
+        let mut malformed  = cb.clone();
+        malformed[0]       = b'X';
+
+        assert_eq!(trust_chain(&mut malformed.as_slice(), &root_key_store, &SystemClock), Err(TrustError::InvalidTrustChain));
+
+        let mut trailing   = cb.clone();
+        trailing.push(0x00);
+
+        assert_eq!(trust_chain(&mut trailing.as_slice(), &root_key_store, &SystemClock), Ok(chain));
+        assert_eq!(trust_chain_strict(&mut trailing.as_slice(), &root_key_store, &SystemClock), Err(TrustError::TrailingData));
+        assert_eq!(trust_chain_ref_strict(&trailing, &root_key_store, &SystemClock).map(|_| ()), Err(TrustError::TrailingData));
+        assert_eq!(trust_chain_ref_strict(&cb, &root_key_store, &SystemClock).map(|c| c.len()), Ok(2));
+
+        struct Broken;
+        impl Read for Broken {
+            fn read (&mut self, _: &mut [u8]) -> io::Result<usize> { Err(io::Error::from(io::ErrorKind::ConnectionReset)) }
+        }
+
+        assert_eq!(trust_chain(&mut Broken, &root_key_store, &SystemClock), Err(TrustError::ReadFailed(io::ErrorKind::ConnectionReset)));
+        assert_eq!(trust_chain_strict(&mut cb.as_slice().chain(Broken), &root_key_store, &SystemClock),
+                   Err(TrustError::ReadFailed(io::ErrorKind::ConnectionReset)));
+    }
 }
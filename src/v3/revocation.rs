@@ -0,0 +1,118 @@
+use ring::signature;
+use crate::verify_signature_v3;
+use crate::v3::{RL_V3_HEADER,PUBLICKEYBYTES,SIGNATUREBYTES};
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use core::convert::TryFrom;
+
+//--- A set of public keys that must no longer be trusted, signed by a root or intermediate key
+#[derive(Clone,Debug,PartialEq)]
+pub struct RevocationList {
+    issuer: PublicKey,
+    revoked: Vec<PublicKey>,
+    sig: Signature,
+}
+
+impl RevocationList {
+    pub fn new (issuer: PublicKey, revoked: Vec<PublicKey>, sig: Signature) -> Result<RevocationList, TrustError> {
+        let signed_data = Self::signed_data(&issuer, &revoked)?;
+        verify_signature_v3!(issuer, sig.as_ref(), signed_data.as_slice())?;
+        Ok(RevocationList {issuer, revoked, sig})
+    }
+
+    // the bytes covered by the issuer's signature: everything in the encoded list up to the signature.
+    pub fn signed_data (issuer: &PublicKey, revoked: &[PublicKey]) -> Result<Vec<u8>, TrustError> {
+        let count = u16::try_from(revoked.len()).map_err(|_| TrustError::InvalidRevocationList)?;
+        let mut data = Vec::with_capacity(4 + PUBLICKEYBYTES + 2 + (PUBLICKEYBYTES * revoked.len()));
+        data.extend_from_slice(&RL_V3_HEADER);
+        data.extend_from_slice(issuer.as_ref());
+        data.extend_from_slice(&count.to_be_bytes());
+        for key in revoked.iter() { data.extend_from_slice(key.as_ref()) }
+        Ok(data)
+    }
+
+    pub fn issuer (&self) -> PublicKey { self.issuer }
+
+    pub fn revoked (&self) -> &[PublicKey] { &self.revoked }
+
+    pub fn sig (&self) -> Signature { self.sig }
+
+    pub fn is_revoked (&self, key: &PublicKey) -> bool {
+        self.revoked.iter().fold(false, |found, revoked| found | (revoked == key))
+    }
+
+    pub fn as_bytes (&self) -> Vec<u8> {
+        // safe because construction rejects lists too long to encode.
+        let mut out = Self::signed_data(&self.issuer, &self.revoked).unwrap();
+        out.reserve_exact(SIGNATUREBYTES);
+        out.extend_from_slice(self.sig.as_ref());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::parsers::revocation_list;
+    use ring::rand::{SystemRandom, SecureRandom};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn gen_keypair(rand: &dyn SecureRandom) -> Option<Ed25519KeyPair> {
+        let mut seed = [0u8; PUBLICKEYBYTES];
+        rand.fill(&mut seed).ok().and_then(|_| Ed25519KeyPair::from_seed_unchecked(&seed).ok())
+    }
+
+    fn public_key(keypair: &Ed25519KeyPair) -> PublicKey {
+        PublicKey::from_slice(keypair.public_key().as_ref()).unwrap()
+    }
+
+    #[test]
+    fn revocation_list_end_to_end_test () {
+        let rand           = SystemRandom::new();
+        let issuer_keypair = gen_keypair(&rand).unwrap();
+        let issuer         = public_key(&issuer_keypair);
+        let revoked        = vec!(public_key(&gen_keypair(&rand).unwrap()), public_key(&gen_keypair(&rand).unwrap()));
+        let signed_data    = RevocationList::signed_data(&issuer, &revoked).unwrap();
+        let sig            = Signature::from(issuer_keypair.sign(&signed_data));
+        let list           = RevocationList::new(issuer, revoked.clone(), sig).unwrap();
+
+        assert!(list.is_revoked(&revoked[0]));
+        assert!(list.is_revoked(&revoked[1]));
+        assert!(!list.is_revoked(&issuer));
+
+        let lb             = list.as_bytes();
+
+        assert_eq!(lb[0..4], RL_V3_HEADER);
+        assert_eq!(lb[36..38], [0x00, 0x02]);// revoked key count
+        assert_eq!(lb.len(), 4 + 32 + 2 + 64 + 64);
+
+        let parsed_list    = revocation_list(&mut lb.as_slice());
+
+        assert_eq!(parsed_list, Ok(list));
+
+        let mut tampered   = lb.clone();
+        tampered[40]      ^= 0x01;
+
+        assert_eq!(revocation_list(&mut tampered.as_slice()), Err(TrustError::InvalidSignature));
+        assert_eq!(revocation_list(&mut &lb[0..lb.len() - 1]), Err(TrustError::UnexpectedEof));
+
+        let mut inflated   = lb.clone();
+        inflated[36..38].copy_from_slice(&[0xff, 0xff]);
+
+        assert_eq!(revocation_list(&mut inflated.as_slice()), Err(TrustError::UnexpectedEof));
+    }
+
+    #[test]
+    fn revocation_list_should_reject_foreign_signature () {
+        let rand           = SystemRandom::new();
+        let issuer_keypair = gen_keypair(&rand).unwrap();
+        let other_keypair  = gen_keypair(&rand).unwrap();
+        let issuer         = public_key(&issuer_keypair);
+        let revoked        = vec!(public_key(&other_keypair));
+        let signed_data    = RevocationList::signed_data(&issuer, &revoked).unwrap();
+        let sig            = Signature::from(other_keypair.sign(&signed_data));
+
+        assert_eq!(RevocationList::new(issuer, revoked, sig), Err(TrustError::InvalidSignature));
+    }
+}
@@ -0,0 +1,158 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use pkcs8::der::{Decode, Encode};
+use pkcs8::pkcs5::{pbes2, scrypt};
+use pkcs8::EncryptedPrivateKeyInfo;
+use zeroize::Zeroizing;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::signer::Signer;
+use core::fmt;
+
+// scrypt cost used when none is given: N = 2^17, r = 8, p = 1, about 128MiB of memory per attempt.
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 17;
+
+const SCRYPT_R     : u32   = 8;
+const SCRYPT_P     : u32   = 1;
+const SALTBYTES    : usize = 16;
+const AES_IV_BYTES : usize = 16;
+
+//--- An Ed25519 private key held as its PKCS#8 document, which is wiped from memory on drop
+pub struct SecretKey {
+    pkcs8: Zeroizing<Vec<u8>>,
+    // ring keeps its own expanded copy of the secret, which it does not expose for zeroizing.
+    keypair: Ed25519KeyPair,
+}
+
+impl SecretKey {
+    pub fn generate () -> Result<SecretKey, TrustError> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| TrustError::KeyGenerationFailed)?;
+        SecretKey::from_pkcs8(pkcs8.as_ref())
+    }
+
+    //--- Takes PKCS#8 v2 documents, which carry the public key, or v1 documents as written by OpenSSL
+    pub fn from_pkcs8 (der: &[u8]) -> Result<SecretKey, TrustError> {
+        // v1 leaves the public key out, so there is nothing to check it against and ring derives it instead.
+        let keypair = Ed25519KeyPair::from_pkcs8(der)
+            .or_else(|_| Ed25519KeyPair::from_pkcs8_maybe_unchecked(der))
+            .map_err(|_| TrustError::InvalidSecretKey)?;
+        Ok(SecretKey { pkcs8: Zeroizing::new(der.to_vec()), keypair })
+    }
+
+    pub fn from_encrypted_pkcs8 (der: &[u8], passphrase: &[u8]) -> Result<SecretKey, TrustError> {
+        let info = EncryptedPrivateKeyInfo::from_der(der).map_err(|_| TrustError::InvalidSecretKey)?;
+        let doc  = info.decrypt(passphrase).map_err(|_| TrustError::KeyDecryptionFailed)?;
+        SecretKey::from_pkcs8(doc.as_bytes()).map_err(|_| TrustError::KeyDecryptionFailed)
+    }
+
+    pub fn public_key (&self) -> PublicKey {
+        // safe because ring always hands back a 32 byte Ed25519 public key.
+        PublicKey::from_slice(self.keypair.public_key().as_ref()).unwrap()
+    }
+
+    pub fn keypair (&self) -> &Ed25519KeyPair {
+        &self.keypair
+    }
+
+    pub fn to_pkcs8 (&self) -> Zeroizing<Vec<u8>> {
+        self.pkcs8.clone()
+    }
+
+    pub fn to_encrypted_pkcs8 (&self, passphrase: &[u8]) -> Result<Vec<u8>, TrustError> {
+        self.to_encrypted_pkcs8_with_cost(passphrase, DEFAULT_SCRYPT_LOG_N)
+    }
+
+    //--- scrypt+AES-256-CBC (PBES2) with the given scrypt work factor, N = 2^log_n
+    pub fn to_encrypted_pkcs8_with_cost (&self, passphrase: &[u8], log_n: u8) -> Result<Vec<u8>, TrustError> {
+        let rand = SystemRandom::new();
+        let mut salt = [0u8;SALTBYTES];
+        let mut iv   = [0u8;AES_IV_BYTES];
+        rand.fill(&mut salt).map_err(|_| TrustError::KeyGenerationFailed)?;
+        rand.fill(&mut iv).map_err(|_| TrustError::KeyGenerationFailed)?;
+
+        let cost   = scrypt::Params::new(log_n, SCRYPT_R, SCRYPT_P, 32).map_err(|_| TrustError::InvalidSecretKey)?;
+        let params = pbes2::Parameters::scrypt_aes256cbc(cost, &salt, &iv).map_err(|_| TrustError::InvalidSecretKey)?;
+        let encrypted_data = params.encrypt(passphrase, &self.pkcs8).map_err(|_| TrustError::InvalidSecretKey)?;
+
+        EncryptedPrivateKeyInfo { encryption_algorithm: params.into(), encrypted_data: &encrypted_data }
+            .to_der()
+            .map_err(|_| TrustError::InvalidSecretKey)
+    }
+}
+
+impl Signer for SecretKey {
+    fn key (&self) -> PublicKey { self.public_key() }
+
+    fn sign (&self, data: &[u8]) -> Result<Signature, TrustError> {
+        Ok(Signature::from(self.keypair.sign(data)))
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey({:?})", self.public_key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // keeps the debug build tests quick, real keys should use the default cost.
+    const TEST_LOG_N: u8 = 10;
+
+    #[test]
+    fn secret_key_should_round_trip_plain_pkcs8 () {
+        let key    = SecretKey::generate().unwrap();
+        let der    = key.to_pkcs8();
+        let loaded = SecretKey::from_pkcs8(&der).unwrap();
+
+        assert_eq!(loaded.public_key(), key.public_key());
+        assert_eq!(loaded.sign(b"test data"), key.sign(b"test data"));
+        assert_eq!(SecretKey::from_pkcs8(&der[1..]).map(|k| k.public_key()), Err(TrustError::InvalidSecretKey));
+    }
+
+    #[test]
+    fn secret_key_should_load_openssl_pkcs8_v1 () {
+        // the RFC 8410 example key, as `openssl genpkey -algorithm ed25519` writes it.
+        let der    = [
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70,
+            0x04, 0x22, 0x04, 0x20, 0xd4, 0xee, 0x72, 0xdb, 0xf9, 0x13, 0x58, 0x4a,
+            0xd5, 0xb6, 0xd8, 0xf1, 0xf7, 0x69, 0xf8, 0xad, 0x3a, 0xfe, 0x7c, 0x28,
+            0xcb, 0xf1, 0xd4, 0xfb, 0xe0, 0x97, 0xa8, 0x8f, 0x44, 0x75, 0x58, 0x42,
+        ];
+        let public = [
+            0x19, 0xbf, 0x44, 0x09, 0x69, 0x84, 0xcd, 0xfe, 0x85, 0x41, 0xba, 0xc1,
+            0x67, 0xdc, 0x3b, 0x96, 0xc8, 0x50, 0x86, 0xaa, 0x30, 0xb6, 0xb6, 0xcb,
+            0x0c, 0x5c, 0x38, 0xad, 0x70, 0x31, 0x66, 0xe1,
+        ];
+        let key    = SecretKey::from_pkcs8(&der).unwrap();
+
+        assert_eq!(key.public_key(), PublicKey::from_slice(&public).unwrap());
+        assert_eq!(key.to_pkcs8().as_slice(), &der[..]);
+        assert_eq!(SecretKey::from_pkcs8(&der[..der.len() - 1]).map(|k| k.public_key()), Err(TrustError::InvalidSecretKey));
+    }
+
+    #[test]
+    fn secret_key_should_round_trip_encrypted_pkcs8 () {
+        let key    = SecretKey::generate().unwrap();
+        let der    = key.to_encrypted_pkcs8_with_cost(b"correct horse", TEST_LOG_N).unwrap();
+
+        assert!(!der.windows(key.to_pkcs8().len()).any(|w| w == key.to_pkcs8().as_slice()));
+
+        let loaded = SecretKey::from_encrypted_pkcs8(&der, b"correct horse").unwrap();
+
+        assert_eq!(loaded.public_key(), key.public_key());
+        assert_eq!(SecretKey::from_encrypted_pkcs8(&der, b"battery staple").map(|k| k.public_key()), Err(TrustError::KeyDecryptionFailed));
+        assert_eq!(SecretKey::from_encrypted_pkcs8(&key.to_pkcs8(), b"correct horse").map(|k| k.public_key()), Err(TrustError::InvalidSecretKey));
+    }
+
+    #[test]
+    fn secret_key_debug_should_not_leak () {
+        let key    = SecretKey::generate().unwrap();
+
+        assert_eq!(format!("{:?}", key), format!("SecretKey({:?})", key.public_key()));
+    }
+}
@@ -0,0 +1,417 @@
+use base64::alphabet::URL_SAFE;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::v3::{MAXCHAINLINKS, PUBLICKEYBYTES, SIGNATUREBYTES, MAXATTRIBUTEBYTES};
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::expiry::{Expiry, NotBefore, NotAfter};
+use crate::v3::attributes::Attributes;
+use crate::v3::link::{Root, Link, Key, Signed, TrustLink};
+use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::trust_chain::TrustChain;
+use core::fmt;
+
+// Keys and signatures are base64url strings in human readable formats (JSON, TOML, YAML) and plain
+// byte strings in binary ones (MessagePack, CBOR, bincode). Padding is left off when writing and
+// tolerated when reading.
+const BASE64URL: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent));
+
+fn serialize_bytes <S: Serializer> (bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&BASE64URL.encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+struct BytesVisitor<T> {
+    expecting: &'static str,
+    max: usize,
+    from_slice: fn(&[u8]) -> Option<T>,
+}
+
+impl <'de, T> Visitor<'de> for BytesVisitor<T> {
+    type Value = T;
+
+    fn expecting (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str <E: de::Error> (self, v: &str) -> Result<T, E> {
+        let bytes = BASE64URL.decode(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_bytes <E: de::Error> (self, v: &[u8]) -> Result<T, E> {
+        (self.from_slice)(v).ok_or_else(|| E::invalid_length(v.len(), &self))
+    }
+
+    // formats without a native byte string hand these over as a sequence of numbers.
+    fn visit_seq <A: SeqAccess<'de>> (self, mut seq: A) -> Result<T, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(self.max));
+        while let Some(b) = seq.next_element::<u8>()? {
+            if bytes.len() == self.max { return Err(de::Error::invalid_length(bytes.len() + 1, &self)) }
+            bytes.push(b);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+fn deserialize_bytes <'de, D: Deserializer<'de>, T> (deserializer: D, visitor: BytesVisitor<T>) -> Result<T, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_ref(), serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for PublicKey {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<PublicKey, D::Error> {
+        deserialize_bytes(deserializer, BytesVisitor { expecting: "a 32 byte Ed25519 public key", max: PUBLICKEYBYTES, from_slice: PublicKey::from_slice })
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_ref(), serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for Signature {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<Signature, D::Error> {
+        deserialize_bytes(deserializer, BytesVisitor { expecting: "a 64 byte Ed25519 signature", max: SIGNATUREBYTES, from_slice: Signature::from_slice })
+    }
+}
+
+impl Serialize for Attributes {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_bytes(), serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for Attributes {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<Attributes, D::Error> {
+        deserialize_bytes(deserializer, BytesVisitor { expecting: "a well formed attribute block", max: MAXATTRIBUTEBYTES, from_slice: |b| Attributes::from_slice(b).ok() })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Expiry")]
+struct ExpiryRepr {
+    not_before: u64,
+    not_after: u64,
+}
+
+impl Serialize for Expiry {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ExpiryRepr { not_before: self.not_before().0, not_after: self.not_after().0 }.serialize(serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for Expiry {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<Expiry, D::Error> {
+        let repr = ExpiryRepr::deserialize(deserializer)?;
+        Expiry::new(NotBefore(repr.not_before), NotAfter(repr.not_after)).map_err(trust_error)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Root")]
+struct RootRepr {
+    key: PublicKey,
+}
+
+impl Serialize for Root {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RootRepr { key: Key::key(self) }.serialize(serializer)
+    }
+}
+
+impl <'de> Deserialize<'de> for Root {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<Root, D::Error> {
+        RootRepr::deserialize(deserializer).map(|repr| Root::new(repr.key))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Link")]
+struct LinkRepr {
+    key: PublicKey,
+    expiry: Option<Expiry>,
+    sig: Signature,
+    // last and left out when empty, so links without attributes keep their old shape in every format.
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    attributes: Attributes,
+}
+
+impl Serialize for Link {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LinkRepr { key: Key::key(self), expiry: TrustLink::expiry(self), sig: Signed::sig(self), attributes: TrustLink::attributes(self) }.serialize(serializer)
+    }
+}
+
+//--- On its own a link is just data; it is only trusted once appended to a verified chain
+impl <'de> Deserialize<'de> for Link {
+    fn deserialize <D: Deserializer<'de>> (deserializer: D) -> Result<Link, D::Error> {
+        let repr = LinkRepr::deserialize(deserializer)?;
+        let link = match repr.expiry {
+            Some(expiry) => Link::with_expiry(repr.key, expiry, repr.sig),
+            None         => Link::new(repr.key, repr.sig),
+        };
+        Ok(link.with_attributes(repr.attributes))
+    }
+}
+
+impl <const N: usize> Serialize for TrustChain<N> {
+    fn serialize <S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let links = self.iter()
+            .skip(1)
+            .map(|l| LinkRepr { key: l.key(), expiry: l.expiry(), sig: l.signature().unwrap(), attributes: l.attributes() })// safe because only the root lacks a signature.
+            .collect::<Vec<_>>();
+        let mut state = serializer.serialize_struct("TrustChain", 2)?;
+        state.serialize_field("root", &RootRepr { key: self.first().key() })?;
+        state.serialize_field("links", &links)?;
+        state.end()
+    }
+}
+
+fn trust_error <E: de::Error> (err: TrustError) -> E {
+    E::custom(format_args!("{:?}", err))
+}
+
+//--- The only way to deserialize a TrustChain: the root must be trusted and every link verified before it is handed out
+pub struct TrustChainSeed<'ks, 'c> {
+    root_keys_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+    clock: &'c dyn Clock,
+}
+
+impl <'ks, 'c> TrustChainSeed<'ks, 'c> {
+    pub fn new (root_keys_store: &'ks (dyn RootKeysStore<'ks> + 'ks), clock: &'c dyn Clock) -> TrustChainSeed<'ks, 'c> {
+        TrustChainSeed { root_keys_store, clock }
+    }
+
+    fn verify (&self, root: Root, links: Vec<Link>) -> Result<TrustChain, TrustError> {
+        let mut chain = TrustChain::new(self.root_keys_store, root, self.clock)?;
+        for link in links {
+            chain = chain.append(link, self.clock)?;
+        }
+        Ok(chain)
+    }
+}
+
+impl <'de, 'ks, 'c> DeserializeSeed<'de> for TrustChainSeed<'ks, 'c> {
+    type Value = TrustChain;
+
+    fn deserialize <D: Deserializer<'de>> (self, deserializer: D) -> Result<TrustChain, D::Error> {
+        deserializer.deserialize_struct("TrustChain", &["root", "links"], self)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ChainField { Root, Links }
+
+impl <'de, 'ks, 'c> Visitor<'de> for TrustChainSeed<'ks, 'c> {
+    type Value = TrustChain;
+
+    fn expecting (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a trust chain with a root and its links")
+    }
+
+    fn visit_seq <A: SeqAccess<'de>> (self, mut seq: A) -> Result<TrustChain, A::Error> {
+        let root  = seq.next_element::<Root>()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let links = seq.next_element_seed(BoundedLinks)?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        self.verify(root, links).map_err(trust_error)
+    }
+
+    // maps may come back in any key order (serde_json::Value sorts them), so both fields are read before verifying.
+    fn visit_map <A: MapAccess<'de>> (self, mut map: A) -> Result<TrustChain, A::Error> {
+        let mut root  = None;
+        let mut links = None;
+        while let Some(field) = map.next_key::<ChainField>()? {
+            match field {
+                ChainField::Root if root.is_some()   => return Err(de::Error::duplicate_field("root")),
+                ChainField::Links if links.is_some() => return Err(de::Error::duplicate_field("links")),
+                ChainField::Root  => root  = Some(map.next_value::<Root>()?),
+                ChainField::Links => links = Some(map.next_value_seed(BoundedLinks)?),
+            }
+        }
+        let root  = root.ok_or_else(|| de::Error::missing_field("root"))?;
+        let links = links.ok_or_else(|| de::Error::missing_field("links"))?;
+        self.verify(root, links).map_err(trust_error)
+    }
+}
+
+// Reads at most the links a chain can hold, so a hostile input can never make us buffer more.
+struct BoundedLinks;
+
+impl <'de> DeserializeSeed<'de> for BoundedLinks {
+    type Value = Vec<Link>;
+
+    fn deserialize <D: Deserializer<'de>> (self, deserializer: D) -> Result<Vec<Link>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl <'de> Visitor<'de> for BoundedLinks {
+    type Value = Vec<Link>;
+
+    fn expecting (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at most {} links", MAXCHAINLINKS - 1)
+    }
+
+    fn visit_seq <A: SeqAccess<'de>> (self, mut seq: A) -> Result<Vec<Link>, A::Error> {
+        let mut links = Vec::with_capacity(MAXCHAINLINKS as usize - 1);
+        while let Some(link) = seq.next_element::<Link>()? {
+            if links.len() == MAXCHAINLINKS as usize - 1 { return Err(trust_error(TrustError::MaxChainLengthExceeded)) }
+            links.push(link);
+        }
+        Ok(links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::builder::{ChainBuilder, issue_link, issue_link_with_attributes};
+    use crate::v3::clock::FixedClock;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn fixture () -> (Vec<PublicKey>, Ed25519KeyPair, TrustChain) {
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x31;32]).unwrap();
+        let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let (chain, end)   = ChainBuilder::new(&root_keypair)
+            .intermediate(None)
+            .build(Some(expiry), &root_key_store, &FixedClock::new(1_500))
+            .unwrap();
+        (root_key_store, end, chain)
+    }
+
+    fn from_json (json: &str, root_key_store: &Vec<PublicKey>, at: u64) -> Result<TrustChain, serde_json::Error> {
+        TrustChainSeed::new(root_key_store, &FixedClock::new(at)).deserialize(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn chain_should_round_trip_through_json () {
+        let (root_key_store, _, chain) = fixture();
+        let json           = serde_json::to_string(&chain).unwrap();
+        let root_b64       = BASE64URL.encode(root_key_store[0].as_ref());
+
+        assert!(json.starts_with(&format!("{{\"root\":{{\"key\":\"{}\"}},\"links\":[", root_b64)));
+        assert!(json.contains("\"expiry\":{\"not_before\":1000,\"not_after\":2000}"));
+        assert!(!json.contains('='));
+        assert_eq!(from_json(&json, &root_key_store, 1_500).unwrap(), chain);
+    }
+
+    #[test]
+    fn chain_should_round_trip_through_msgpack_as_raw_bytes () {
+        let (root_key_store, _, chain) = fixture();
+        let packed         = rmp_serde::to_vec(&chain).unwrap();
+        let named          = rmp_serde::to_vec_named(&chain).unwrap();
+
+        // the root key goes in as a 32 byte bin8 (0xc4, 0x20), not as a base64 string.
+        assert!(packed.windows(34).any(|w| w[..2] == [0xc4, 0x20] && w[2..] == *root_key_store[0].as_ref()));
+
+        for bytes in [packed, named].iter() {
+            let mut de     = rmp_serde::Deserializer::new(bytes.as_slice());
+
+            assert_eq!(TrustChainSeed::new(&root_key_store, &FixedClock::new(1_500)).deserialize(&mut de).unwrap(), chain);
+        }
+    }
+
+    #[test]
+    fn deserialized_chain_should_always_be_verified () {
+        let (root_key_store, end, chain) = fixture();
+        let json           = serde_json::to_string(&chain).unwrap();
+
+        assert!(from_json(&json, &Vec::new(), 1_500).unwrap_err().to_string().contains("NoRootKeyTrust"));
+        assert!(from_json(&json, &root_key_store, 2_001).unwrap_err().to_string().contains("InvalidExpiry"));
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["links"][1]["expiry"]["not_after"] = 3_000.into();
+
+        assert!(from_json(&value.to_string(), &root_key_store, 1_500).unwrap_err().to_string().contains("InvalidSignature"));
+
+        value["links"][1]["expiry"]["not_before"] = 4_000.into();
+
+        assert!(from_json(&value.to_string(), &root_key_store, 1_500).unwrap_err().to_string().contains("InvalidExpiry"));
+
+        let end_key        = PublicKey::from_slice(end.public_key().as_ref()).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for _ in 0..3 {
+            let extra      = serde_json::to_value(issue_link(&end, end_key, None).unwrap()).unwrap();
+            value["links"].as_array_mut().unwrap().push(extra);
+        }
+
+        assert!(from_json(&value.to_string(), &root_key_store, 1_500).unwrap_err().to_string().contains("MaxChainLengthExceeded"));
+
+        // object keys may arrive in any order, serde_json::Value itself sorts them.
+        let original: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let reordered      = format!("{{\"links\":{},\"root\":{}}}", original["links"], original["root"]);
+
+        assert_eq!(from_json(&reordered, &root_key_store, 1_500).unwrap(), chain);
+        assert!(from_json(&format!("{{\"root\":{}}}", original["root"]), &root_key_store, 1_500).unwrap_err().to_string().contains("missing field `links`"));
+    }
+
+    #[test]
+    fn link_attributes_should_round_trip_only_when_present () {
+        let root_keypair   = Ed25519KeyPair::from_seed_unchecked(&[0x32;32]).unwrap();
+        let root_key_store = vec!(PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap());
+        let clock          = FixedClock::new(1_500);
+        let end_key        = PublicKey::from_slice(&[0x07;32]).unwrap();
+        let attributes     = Attributes::new().with_label("device 7").unwrap();
+        let root_only      = TrustChain::new(&root_key_store, Root::new(root_key_store[0]), &clock).unwrap();
+        let legacy         = root_only.clone().append(issue_link(&root_keypair, end_key, None).unwrap(), &clock).unwrap();
+        let attributed     = root_only.append(issue_link_with_attributes(&root_keypair, end_key, None, attributes).unwrap(), &clock).unwrap();
+
+        assert!(!serde_json::to_string(&legacy).unwrap().contains("attributes"));
+
+        let json           = serde_json::to_string(&attributed).unwrap();
+        let packed         = rmp_serde::to_vec(&attributed).unwrap();
+
+        assert!(json.contains(&format!("\"attributes\":\"{}\"", BASE64URL.encode(attributes.as_bytes()))));
+        assert_eq!(from_json(&json, &root_key_store, 1_500).unwrap(), attributed);
+        assert_eq!(TrustChainSeed::new(&root_key_store, &FixedClock::new(1_500)).deserialize(&mut rmp_serde::Deserializer::new(packed.as_slice())).unwrap(), attributed);
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["links"][0]["attributes"] = BASE64URL.encode(Attributes::new().with_label("device 8").unwrap().as_bytes()).into();
+
+        assert!(from_json(&value.to_string(), &root_key_store, 1_500).unwrap_err().to_string().contains("InvalidSignature"));
+
+        value["links"][0]["attributes"] = BASE64URL.encode([0x02, 0x00]).into();
+
+        assert!(from_json(&value.to_string(), &root_key_store, 1_500).is_err());
+    }
+
+    #[test]
+    fn keys_and_windows_should_be_validated () {
+        let key            = PublicKey::from_slice(&[0x07;32]).unwrap();
+        let encoded        = serde_json::to_string(&key).unwrap();
+
+        assert_eq!(encoded, format!("\"{}\"", BASE64URL.encode([0x07;32])));
+        assert_eq!(serde_json::from_str::<PublicKey>(&encoded).unwrap(), key);
+        assert_eq!(serde_json::from_str::<PublicKey>(&format!("\"{}=\"", BASE64URL.encode([0x07;32]))).unwrap(), key);
+        assert!(serde_json::from_str::<PublicKey>(&format!("\"{}\"", BASE64URL.encode([0x07;31]))).is_err());
+        assert!(serde_json::from_str::<PublicKey>("\"not*base64\"").is_err());
+        assert!(serde_json::from_str::<Signature>(&encoded).is_err());
+        assert!(serde_json::from_str::<Expiry>("{\"not_before\":2,\"not_after\":1}").is_err());
+        assert_eq!(serde_json::from_str::<Expiry>("{\"not_before\":1,\"not_after\":2}").unwrap(),
+                   Expiry::new(NotBefore(1), NotAfter(2)).unwrap());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::v3::key::PublicKey;
+use crate::v3::fingerprint::Fingerprint;
+use crate::v3::anchor::TrustAnchor;
+use crate::v3::keystore::RootKeysStore;
+use std::sync::{Arc, Mutex, RwLock};
+
+//--- A root keys store that can be shared between threads and replaced while in use
+//
+// Readers work on an Arc snapshot, so a swap never changes the keys under a verification that is
+// already running: it keeps the snapshot it started with and the next lookup sees the new one.
+#[derive(Debug)]
+pub struct SharedKeysStore<S> {
+    current: RwLock<Arc<S>>,
+    // serialises update() so two reloads cannot both build on the same snapshot and lose one another.
+    writer: Mutex<()>,
+}
+
+impl <S> SharedKeysStore<S> {
+    pub fn new (store: S) -> SharedKeysStore<S> {
+        SharedKeysStore { current: RwLock::new(Arc::new(store)), writer: Mutex::new(()) }
+    }
+
+    //--- the store as it is right now, unaffected by later swaps
+    pub fn snapshot (&self) -> Arc<S> {
+        // the lock only ever guards a pointer swap, so a poisoned lock still holds a whole snapshot.
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    //--- installs a new store and hands back the one it replaced
+    pub fn swap (&self, store: S) -> Arc<S> {
+        let _writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        self.replace(Arc::new(store))
+    }
+
+    //--- Builds the next store from the current one, e.g. by reloading it, without blocking readers while
+    // it runs. Returns the installed store; nothing is swapped when `next` fails.
+    pub fn update <F, E> (&self, next: F) -> Result<Arc<S>, E>
+    where F: FnOnce(&S) -> Result<S, E> {
+        let _writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let store   = next(&self.snapshot())?;
+        self.replace(Arc::new(store));
+        Ok(self.snapshot())
+    }
+
+    fn replace (&self, store: Arc<S>) -> Arc<S> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, store)
+    }
+}
+
+impl <'ks, S> RootKeysStore<'ks> for SharedKeysStore<S>
+where S: for<'s> RootKeysStore<'s> {
+    fn trust_anchor <'a> (&'ks self, candidate: &'a [u8]) -> Option<TrustAnchor> {
+        self.snapshot().trust_anchor(candidate)
+    }
+
+    fn root_key_by_fingerprint (&'ks self, fingerprint: &Fingerprint) -> Option<PublicKey> {
+        self.snapshot().root_key_by_fingerprint(fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v3::PUBLICKEYBYTES;
+    use crate::v3::builder::ChainBuilder;
+    use crate::v3::clock::FixedClock;
+    use crate::v3::error::TrustError;
+    use crate::v3::link::Root;
+    use crate::v3::parsers::trust_chain;
+    use crate::v3::trust_chain::TrustChain;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use std::sync::Barrier;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use super::*;
+
+    fn key (fill: u8) -> PublicKey {
+        PublicKey::from_slice(&[fill;PUBLICKEYBYTES]).unwrap()
+    }
+
+    fn assert_send_sync <T: Send + Sync> () {}
+
+    #[test]
+    fn shared_store_should_be_send_and_sync () {
+        assert_send_sync::<SharedKeysStore<Vec<PublicKey>>>();
+        assert_send_sync::<Arc<SharedKeysStore<crate::v3::dir_store::DirectoryKeysStore>>>();
+    }
+
+    #[test]
+    fn snapshots_should_survive_swaps () {
+        let store  = SharedKeysStore::new(vec!(key(0x01)));
+        let before = store.snapshot();
+        let old    = store.swap(vec!(key(0x02)));
+
+        assert!(Arc::ptr_eq(&before, &old));
+        assert_eq!(*before, vec!(key(0x01)));
+        assert!(!store.contains_root_key(key(0x01).as_ref()));
+        assert!(store.contains_root_key(key(0x02).as_ref()));
+        assert_eq!(store.root_key_by_fingerprint(&key(0x02).fingerprint()), Some(key(0x02)));
+
+        let failed: Result<_, ()> = store.update(|_| Err(()));
+
+        assert_eq!(failed, Err(()));
+        assert_eq!(*store.snapshot(), vec!(key(0x02)));
+    }
+
+    #[test]
+    fn readers_should_always_see_a_whole_snapshot () {
+        // every snapshot holds either both of the first pair of keys or both of the second, never a mix.
+        let store  = Arc::new(SharedKeysStore::new(vec!(key(0x01), key(0x02))));
+        let done   = Arc::new(AtomicBool::new(false));
+        let start  = Arc::new(Barrier::new(5));
+
+        let writer = {
+            let (store, done, start) = (store.clone(), done.clone(), start.clone());
+            thread::spawn(move || {
+                start.wait();
+                let mut swaps = 0;
+                while !done.load(Ordering::Relaxed) {
+                    store.swap(if swaps % 2 == 0 { vec!(key(0x03), key(0x04)) } else { vec!(key(0x01), key(0x02)) });
+                    swaps += 1;
+                }
+            })
+        };
+
+        let readers = (0..4).map(|_| {
+            let (store, start) = (store.clone(), start.clone());
+            thread::spawn(move || {
+                start.wait();
+                for _ in 0..5_000 {
+                    let snap = store.snapshot();
+                    let has  = |fill: u8| snap.contains_root_key(key(fill).as_ref());
+                    assert!((has(0x01) && has(0x02) && !has(0x03) && !has(0x04)) || (!has(0x01) && !has(0x02) && has(0x03) && has(0x04)));
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        for reader in readers { reader.join().unwrap() }
+        done.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn verifications_should_run_while_roots_are_rolled () {
+        let old_root   = Ed25519KeyPair::from_seed_unchecked(&[0x18;32]).unwrap();
+        let new_root   = Ed25519KeyPair::from_seed_unchecked(&[0x81;32]).unwrap();
+        let old_key    = PublicKey::from_slice(old_root.public_key().as_ref()).unwrap();
+        let new_key    = PublicKey::from_slice(new_root.public_key().as_ref()).unwrap();
+        let clock      = FixedClock::new(1_500);
+        let (chain, _) = ChainBuilder::new(&new_root).intermediate(None).build(None, &vec!(new_key), &clock).unwrap();
+        let encoded    = Arc::new(chain.as_bytes());
+
+        // the new root is trusted throughout; the old one comes and goes.
+        let store  = Arc::new(SharedKeysStore::new(vec!(old_key, new_key)));
+        let done   = Arc::new(AtomicBool::new(false));
+
+        let verifiers = (0..4).map(|_| {
+            let (store, done, encoded, chain) = (store.clone(), done.clone(), encoded.clone(), chain.clone());
+            thread::spawn(move || {
+                let clock = FixedClock::new(1_500);
+                while !done.load(Ordering::Relaxed) {
+                    assert_eq!(trust_chain(&mut encoded.as_slice(), &*store, &clock).as_ref(), Ok(&chain));
+                    let old = TrustChain::new(&*store, Root::new(old_key), &clock);
+                    assert!(old.is_ok() || old == Err(TrustError::NoRootKeyTrust));
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        let updaters = (0..2).map(|_| {
+            let store = store.clone();
+            thread::spawn(move || {
+                for _ in 0..500 {
+                    store.update(|current| -> Result<_, ()> {
+                        Ok(if current.len() == 2 { vec!(new_key) } else { vec!(old_key, new_key) })
+                    }).unwrap();
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        for updater in updaters { updater.join().unwrap() }
+        done.store(true, Ordering::Relaxed);
+        for verifier in verifiers { verifier.join().unwrap() }
+
+        // 1000 serialised toggles from two keys end back on two keys.
+        assert_eq!(*store.snapshot(), vec!(old_key, new_key));
+    }
+
+    #[test]
+    fn concurrent_updates_should_not_be_lost () {
+        let store  = Arc::new(SharedKeysStore::new(Vec::<PublicKey>::new()));
+
+        let writers = (0..8u8).map(|t| {
+            let store = store.clone();
+            thread::spawn(move || {
+                for i in 0..50u8 {
+                    store.update(|current| -> Result<_, ()> {
+                        let mut next = current.clone();
+                        next.push(PublicKey::from_slice(&[t, i].repeat(PUBLICKEYBYTES / 2)).unwrap());
+                        Ok(next)
+                    }).unwrap();
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        for writer in writers { writer.join().unwrap() }
+
+        assert_eq!(store.snapshot().len(), 8 * 50);
+    }
+}
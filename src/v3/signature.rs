@@ -18,7 +18,7 @@ impl Signature {
 impl cmp::PartialEq for Signature {
     fn eq(&self, other: &Self) -> bool {
         let mut acc = 0;
-        for (l,r) in self.0.iter().zip(other.0.iter()) {acc ^= *l ^ *r;}
+        for (l,r) in self.0.iter().zip(other.0.iter()) {acc |= *l ^ *r;}
         acc == 0
     }
 }
@@ -41,3 +41,18 @@ impl From<ring::signature::Signature> for Signature {
         sig
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_differing_in_cancelling_bytes_should_not_be_equal () {
+        let mut other = [0u8;SIGNATUREBYTES];
+        other[0] = 0x01;
+        other[1] = 0x01;
+
+        assert_ne!(Signature([0u8;SIGNATUREBYTES]), Signature(other));
+        assert_eq!(Signature(other), Signature(other));
+    }
+}
@@ -0,0 +1,37 @@
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+
+//--- The private key side of a chain participant: wherever the secret lives, it can name its public key and sign bytes
+pub trait Signer {
+    fn key  (&self) -> PublicKey;
+    fn sign (&self, data: &[u8]) -> Result<Signature, TrustError>;
+}
+
+impl Signer for Ed25519KeyPair {
+    fn key (&self) -> PublicKey {
+        // safe because ring always hands back a 32 byte Ed25519 public key.
+        PublicKey::from_slice(KeyPair::public_key(self).as_ref()).unwrap()
+    }
+
+    fn sign (&self, data: &[u8]) -> Result<Signature, TrustError> {
+        Ok(Signature::from(Ed25519KeyPair::sign(self, data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{self, UnparsedPublicKey};
+    use super::*;
+
+    #[test]
+    fn keypair_signer_should_sign () {
+        let keypair = Ed25519KeyPair::from_seed_unchecked(&[0x07;32]).unwrap();
+        let signer: &dyn Signer = &keypair;
+        let sig     = signer.sign(b"test data").unwrap();
+
+        assert_eq!(signer.key().as_ref(), keypair.public_key().as_ref());
+        assert!(UnparsedPublicKey::new(&signature::ED25519, signer.key()).verify(b"test data", sig.as_ref()).is_ok());
+    }
+}
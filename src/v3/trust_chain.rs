@@ -1,12 +1,23 @@
 use ring::signature;
-use crate::v3::TC_V3_HEADER;
+use crate::v3::{TC_V3_HEADER,TC_V3_FLAGS_HEADER,PUBLICKEYBYTES,SIGNATUREBYTES,EXPIRYBYTES,MAXATTRIBUTEBYTES,MAXCHAINLINKS};
 use crate::v3::error::TrustError;
 use crate::v3::signature::Signature;
-use crate::v3::link::{Root, Link, TrustLink, Signed};
+use crate::v3::link::{Root, Link, TrustLink, Signed, LINK_FLAG_EXPIRY, LINK_FLAG_ATTRIBUTES};
+use crate::v3::anchor::TrustAnchor;
+use crate::v3::attributes::Attributes;
 use crate::v3::keystore::RootKeysStore;
+use crate::v3::clock::Clock;
+use crate::v3::revocation::RevocationList;
+use crate::v3::signer::Signer;
 use core::convert::AsRef;
 use core::fmt;
+use std::borrow::Cow;
 use std::convert::TryInto;
+use std::io::{self, Write};
+
+// a chain of MAXCHAINLINKS links, each carrying a validity window and a full attribute block.
+pub(crate) const MAX_ENCODED_LEN: usize =
+    4 + 1 + PUBLICKEYBYTES + (MAXCHAINLINKS as usize - 1) * (PUBLICKEYBYTES + 1 + EXPIRYBYTES + 2 + MAXATTRIBUTEBYTES + SIGNATUREBYTES);
 
 #[macro_export]
 macro_rules! verify_signature_v3 {
@@ -17,75 +28,64 @@ macro_rules! verify_signature_v3 {
     };
 }
 
-#[macro_export]
-macro_rules! sign_v3 {
-    ($keypair:expr, $data:expr) => {
-        Signature::from_slice($keypair.sign(&[$data].concat()).as_ref());
-    };
-}
-
+//--- Builds a chain of the default capacity, so links past MAXCHAINLINKS fail with MaxChainLengthExceeded when appended
 #[macro_export]
 macro_rules! trust_chain_v3 {
-    ($root_key_store:expr, $root:expr $(, $link:expr)*) => {
-        TrustChain::new($root_key_store, $root)
-            $(.and_then(|chain| chain.append($link)))*
-    };
-    //TODO:: the above case replaced all 5 of the cases below, however
-    //       we lost the ability to bound the number of links at compile time
-    //       and while construction will fail with a MaxChainLengthExceeded TrustError
-    //       it would be better to bound the $link instances.
-/*
-    ($root_key_store:expr, $root:expr) => {
-        TrustChain::new($root_key_store, $root)
-    };
-    ($root_key_store:expr, $root:expr, $link1:expr) => {
-        TrustChain::new($root_key_store, $root)
-            .and_then(|chain| chain.append($link1))
-    };
-    ($root_key_store:expr, $root:expr, $link1:expr, $link2:expr) => {
-        TrustChain::new($root_key_store, $root)
-            .and_then(|chain| chain.append($link1))
-            .and_then(|chain| chain.append($link2))
-    };
-    ($root_key_store:expr, $root:expr, $link1:expr, $link2:expr, $link3:expr) => {
-        TrustChain::new($root_key_store, $root)
-            .and_then(|chain| chain.append($link1))
-            .and_then(|chain| chain.append($link2))
-            .and_then(|chain| chain.append($link3))
-    };
-    ($root_key_store:expr, $root:expr, $link1:expr, $link2:expr, $link3:expr, $link4:expr) => {
-        TrustChain::new($root_key_store, $root)
-            .and_then(|chain| chain.append($link1))
-            .and_then(|chain| chain.append($link2))
-            .and_then(|chain| chain.append($link3))
-            .and_then(|chain| chain.append($link4))
+    ($root_key_store:expr, $clock:expr, $root:expr $(, $link:expr)*) => {
+        TrustChain::new($root_key_store, $root, $clock)
+            $(.and_then(|chain| chain.append($link, $clock)))*
     };
-*/
 }
 
-#[derive(Copy,Clone,Debug,PartialEq)]
-pub enum TrustChain {
-    TC01(Root),
-    TC02(Root, Link),
-    TC03(Root, Link, Link),
-    TC04(Root, Link, Link, Link),
-    TC05(Root, Link, Link, Link, Link),
+//--- A verified chain: the anchor that vouched for the root, then up to N - 1 links beneath it
+//
+// N counts the root as well, like MAXCHAINLINKS, and is fixed by the type so a chain never allocates.
+// Chains deeper than MAXCHAINLINKS only read back through the parsers' _bounded forms at the same N.
+#[derive(Clone,PartialEq)]
+pub struct TrustChain<const N: usize = { MAXCHAINLINKS as usize }> {
+    anchor: TrustAnchor,
+    len: usize,
+    // indexed by depth, so the root's slot is always empty and the anchor stands in for it.
+    links: [Option<Link>; N],
 }
 
 impl TrustChain {
-
+    //--- Starts a chain at a root the store holds an anchor for, provided the anchor admits it at this time
     pub fn new <'ks> (
         root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
-        root: Root ) -> Result<TrustChain, TrustError> {
+        root: Root,
+        clock: &dyn Clock ) -> Result<TrustChain, TrustError> {
 
-        if !root_key_store.contains_root_key(root.key().as_ref()) {
-            return Err(TrustError::NoRootKeyTrust)
-        }
+        TrustChain::bounded(root_key_store, root, clock)
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl <const N: usize> TrustChain<N> {
+    // the length goes out as a single byte, and every chain holds at least its root.
+    pub(crate) const CAPACITY: () = assert!(N >= 1 && N <= u8::MAX as usize, "a trust chain holds from 1 to 255 links");
 
-        Ok(Self::TC01(root))
+    //--- As new, for a chain of any capacity
+    pub fn bounded <'ks> (
+        root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+        root: Root,
+        clock: &dyn Clock ) -> Result<TrustChain<N>, TrustError> {
+
+        // naming the constant is what makes the compiler check it for this N.
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::CAPACITY;
+        let anchor = root_key_store.trust_anchor(root.key().as_ref()).ok_or(TrustError::NoRootKeyTrust)?;
+        anchor.admits_at(clock.now())?;
+
+        Ok(TrustChain { anchor, len: 1, links: [None; N] })
     }
 
-    pub fn iter <'c> (&self) -> TrustChainIterator {
+    //--- the anchor that validated this chain
+    pub fn anchor (&self) -> &TrustAnchor {
+        &self.anchor
+    }
+
+    pub fn iter (&self) -> TrustChainIterator<'_, N> {
         TrustChainIterator {
             chain: self,
             index: 0
@@ -93,105 +93,198 @@ impl TrustChain {
     }
 
     pub fn len (&self) -> usize {
-        use TrustChain::*;
-        match self {
-            TC01(_)         => 1,
-            TC02(_,_)       => 2,
-            TC03(_,_,_)     => 3,
-            TC04(_,_,_,_)   => 4,
-            TC05(_,_,_,_,_) => 5,
-        }
+        self.len
+    }
+
+    //--- the most links, root included, this chain can hold
+    pub fn capacity (&self) -> usize {
+        N
+    }
 
+    pub fn first (&self) -> &dyn TrustLink {
+        self.anchor()
     }
-    pub fn first <'c> (&'c self) -> &'c dyn TrustLink {
-        use TrustChain::*;
-        match self {
-            TC01(l)         => l,
-            TC02(l,_)       => l,
-            TC03(l,_,_)     => l,
-            TC04(l,_,_,_)   => l,
-            TC05(l,_,_,_,_) => l,
+
+    pub fn last (&self) -> &dyn TrustLink {
+        self.link(self.len - 1)
+    }
+
+    fn link (&self, depth: usize) -> &dyn TrustLink {
+        match &self.links[depth] {
+            Some(l) => l,
+            None    => &self.anchor,
         }
     }
-    pub fn last <'c> (&'c self) -> &'c dyn TrustLink {
-        use TrustChain::*;
-        match self {
-            TC01(l)         => l,
-            TC02(_,l)       => l,
-            TC03(_,_,l)     => l,
-            TC04(_,_,_,l)   => l,
-            TC05(_,_,_,_,l) => l,
+
+    pub fn append (mut self, l:Link, clock: &dyn Clock) -> Result<TrustChain<N>, TrustError> {
+        let signed_data = Link::signed_data_with_attributes(&l.key(), l.expiry().as_ref(), &l.attributes());
+        verify_signature_v3!(self.last().key(),l.sig().as_ref(),signed_data.as_slice())?;
+
+        if !self.last().attributes().permits_link_signing() { return Err(TrustError::LinkSigningNotPermitted) }
+
+        if let Some(expiry) = l.expiry() {
+            if !expiry.is_valid_at(clock.now()) { return Err(TrustError::InvalidExpiry) }
         }
+
+        self.anchor().allows_depth(self.len())?;
+        if self.depth_limit().is_some_and(|max| self.len() > max) { return Err(TrustError::PathLengthExceeded) }
+
+        if self.len == N { return Err(TrustError::MaxChainLengthExceeded) }
+        self.links[self.len] = Some(l);
+        self.len += 1;
+        Ok(self)
     }
 
-    pub fn append (self, l:Link) -> Result<TrustChain, TrustError> {
-        use TrustChain::*;
+    //--- The deepest a link may sit beneath the root, the tightest of the max depths set by the links so far
+    pub fn depth_limit (&self) -> Option<usize> {
+        self.iter()
+            .enumerate()
+            .filter_map(|(depth, l)| l.attributes().max_depth().map(|max| depth + max as usize))
+            .min()
+    }
 
-        verify_signature_v3!(self.last().key(),l.sig().as_ref(),l.key().as_ref())?;
+    pub fn is_valid_at (&self, at:u64) -> Result<(),TrustError> {
+        self.anchor().is_valid_at(at)?;
+        for l in self.iter() {
+            if let Some(expiry) = l.expiry() {
+                if !expiry.is_valid_at(at) { return Err(TrustError::InvalidExpiry) }
+            }
+        }
+        Ok(())
+    }
 
-        match self {
-            TC01(r)         => Ok(TC02(r,l)),
-            TC02(r,a)       => Ok(TC03(r,a,l)),
-            TC03(r,a,b)     => Ok(TC04(r,a,b,l)),
-            TC04(r,a,b,c)   => Ok(TC05(r,a,b,c,l)),
-            TC05(_,_,_,_,_) => Err(TrustError::MaxChainLengthExceeded),
+    // a list issued by a trusted root covers every link in the chain, a list issued by a key within
+    // the chain covers that key and the links beneath it, any other list has no say over this chain.
+    pub fn verify_revocations <'ks> (
+        &self,
+        root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+        revocation_lists: &[RevocationList] ) -> Result<(),TrustError> {
+
+        for list in revocation_lists.iter() {
+            let issuer = list.issuer();
+            let mut authoritative = root_key_store.contains_root_key(issuer.as_ref());
+            for l in self.iter() {
+                authoritative |= l.key() == issuer;
+                if authoritative && list.is_revoked(&l.key()) {
+                    return Err(TrustError::RevokedKey(l.key()))
+                }
+            }
         }
+        Ok(())
     }
 
-    pub fn verify_data (&self, untrusted_signature: &Signature, untrusted_data: &[u8]) -> Result<(),TrustError> {
+    //--- Opt-in check that every critical attribute in the chain is standard or named in `understood`
+    pub fn verify_critical_attributes (&self, understood: &[u8]) -> Result<(),TrustError> {
+        self.iter().try_for_each(|l| l.attributes().check_critical(understood))
+    }
+
+    pub fn verify_data (&self, untrusted_signature: &Signature, untrusted_data: &[u8], clock: &dyn Clock) -> Result<(),TrustError> {
+        self.is_valid_at(clock.now())?;
+        if !self.last().attributes().permits_data_signing() { return Err(TrustError::DataSigningNotPermitted) }
         verify_signature_v3!(self.last().key(),untrusted_signature.as_ref(), untrusted_data)
     }
 
+    //--- Signs data as the holder of this chain's end key, refusing any other signer
+    pub fn sign_data (&self, signer: &dyn Signer, data: &[u8]) -> Result<Signature,TrustError> {
+        if signer.key() != self.last().key() { return Err(TrustError::SigningFailed) }
+        if !self.last().attributes().permits_data_signing() { return Err(TrustError::DataSigningNotPermitted) }
+        signer.sign(data)
+    }
+
+    pub fn encoded_len (&self) -> usize {
+        let flags = if self.is_flagged() { 1 } else { 0 };
+        let links = self.iter()
+            .skip(1)
+            .map(|l| PUBLICKEYBYTES + flags + l.expiry().map_or(0, |_| EXPIRYBYTES) + attributes_len(&l.attributes()) + SIGNATUREBYTES)
+            .sum::<usize>();
+        TC_V3_HEADER.len() + 1 + PUBLICKEYBYTES + links
+    }
+
+    //--- The whole encoding goes out in a single write_all, assembled on the stack
+    pub fn write_to <W: Write> (&self, out: &mut W) -> io::Result<()> {
+        let mut buf = [0u8;MAX_ENCODED_LEN];
+        out.write_all(&self.encode_with(&mut buf))
+    }
+
     pub fn as_bytes (&self) -> Vec<u8> {
-        let mut out:Vec<u8> = Vec::with_capacity(4 + 1 + 32 + ( (32 + 64) * (self.len() -1) ));
-        for b in TC_V3_HEADER.iter() { out.push(*b) }
-        out.push(self.len().try_into().unwrap());// safe because we prevent creation beyond max chain length.
+        let mut buf = vec!(0u8; self.encoded_len());
+        self.encode(&mut buf);
+        buf
+    }
+
+    // chains within MAXCHAINLINKS always fit the stack buffer, only deeper capacities need the heap.
+    pub(crate) fn encode_with <'b> (&self, buf: &'b mut [u8;MAX_ENCODED_LEN]) -> Cow<'b, [u8]> {
+        if self.encoded_len() > MAX_ENCODED_LEN { return Cow::Owned(self.as_bytes()) }
+        let len = self.encode(buf);
+        Cow::Borrowed(&buf[..len])
+    }
+
+    // only a chain holding a link with an expiry or attributes needs the flags byte, every other
+    // chain keeps the plain v3 layout, byte for byte as it was written before links had flags.
+    fn is_flagged (&self) -> bool {
+        self.iter().any(|l| l.expiry().is_some() || !l.attributes().is_empty())
+    }
+
+    fn encode (&self, buf: &mut [u8]) -> usize {
+        let flagged = self.is_flagged();
+        buf[..4].copy_from_slice(if flagged { &TC_V3_FLAGS_HEADER } else { &TC_V3_HEADER });
+        buf[4] = self.len().try_into().unwrap();// safe because CAPACITY keeps N within a byte.
+        let mut at = 5;
+        let mut put = |bytes: &[u8]| {
+            buf[at..at + bytes.len()].copy_from_slice(bytes);
+            at += bytes.len();
+        };
         for l in self.iter() {
-            if l.is_root() {
-                for b in l.key().as_ref().iter() { out.push(*b) }
-            } else {
-                for b in l.key().as_ref().iter() { out.push(*b) }
-                for b in l.signature().unwrap().as_ref().iter() { out.push(*b) }
+            put(l.key().as_ref());
+            if !l.is_root() {
+                let attributes = l.attributes();
+                let expiry     = l.expiry();
+                if flagged {
+                    put(&[expiry.map_or(0, |_| LINK_FLAG_EXPIRY) | if attributes.is_empty() { 0 } else { LINK_FLAG_ATTRIBUTES }]);
+                }
+                if let Some(expiry) = expiry { put(&expiry.as_bytes()) }
+                if !attributes.is_empty() {
+                    put(&(attributes.as_bytes().len() as u16).to_be_bytes());
+                    put(attributes.as_bytes());
+                }
+                put(l.signature().unwrap().as_ref());
             }
         }
-        out
+        at
     }
 
 }
 
-pub struct TrustChainIterator<'c> {
-    chain: &'c TrustChain,
-    index: u8,
+// the length prefix and the block, or nothing at all when a link carries no attributes.
+fn attributes_len (attributes: &Attributes) -> usize {
+    if attributes.is_empty() { 0 } else { 2 + attributes.as_bytes().len() }
+}
+
+pub struct TrustChainIterator<'c, const N: usize = { MAXCHAINLINKS as usize }> {
+    chain: &'c TrustChain<N>,
+    index: usize,
 }
 
-impl <'c> Iterator for TrustChainIterator<'c> {
+impl <'c, const N: usize> Iterator for TrustChainIterator<'c, N> {
     type Item = &'c dyn TrustLink;
 
     fn next(&mut self) -> Option<&'c dyn TrustLink> {
-        use TrustChain::*;
-
-        match (self.index, self.chain) {
-            (0, TC01(l))         => { self.index += 1; Some(l)},
-            (0, TC02(l,_))       => { self.index += 1; Some(l)},
-            (0, TC03(l,_,_))     => { self.index += 1; Some(l)},
-            (0, TC04(l,_,_,_))   => { self.index += 1; Some(l)},
-            (0, TC05(l,_,_,_,_)) => { self.index += 1; Some(l)},
-            (1, TC02(_,l))       => { self.index += 1; Some(l)},
-            (1, TC03(_,l,_))     => { self.index += 1; Some(l)},
-            (1, TC04(_,l,_,_))   => { self.index += 1; Some(l)},
-            (1, TC05(_,l,_,_,_)) => { self.index += 1; Some(l)},
-            (2, TC03(_,_,l))     => { self.index += 1; Some(l)},
-            (2, TC04(_,_,l,_))   => { self.index += 1; Some(l)},
-            (2, TC05(_,_,l,_,_)) => { self.index += 1; Some(l)},
-            (3, TC04(_,_,_,l))   => { self.index += 1; Some(l)},
-            (3, TC05(_,_,_,l,_)) => { self.index += 1; Some(l)},
-            (4, TC05(_,_,_,_,l)) => { self.index += 1; Some(l)},
-            _ => None
-        }
+        if self.index == self.chain.len { return None }
+        self.index += 1;
+        Some(self.chain.link(self.index - 1))
     }
 }
 
-impl fmt::Display for TrustChain {
+impl <const N: usize> fmt::Debug for TrustChain<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TrustChain")
+            .field("anchor", &self.anchor)
+            .field("links", &&self.links[1..self.len])
+            .finish()
+    }
+}
+
+impl <const N: usize> fmt::Display for TrustChain<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let hex_str = self.as_bytes()
             .iter()
@@ -206,6 +299,10 @@ mod tests {
     use crate::v3::PUBLICKEYBYTES;
     use crate::v3::key::PublicKey;
     use crate::v3::link::{Root, Link};
+    use crate::v3::expiry::{Expiry, NotBefore, NotAfter};
+    use crate::v3::clock::{SystemClock, FixedClock};
+    use crate::v3::builder::{ChainBuilder, issue_link};
+    use chrono::{TimeZone, Utc};
     use ring::rand::{SystemRandom, SecureRandom};
     use ring::signature;
     use ring::signature::KeyPair;
@@ -213,8 +310,8 @@ mod tests {
 
     macro_rules! assert_err {
         ($res:expr) => {{
-            if $res.is_ok() {
-                assert!(false, format!("{:?}",$res.unwrap()));
+            if let Ok(v) = &$res {
+                panic!("{:?}", v);
             }
         }};
     }
@@ -222,8 +319,8 @@ mod tests {
 
     macro_rules! assert_ok {
         ($res:expr) => {{
-            if $res.is_err() {
-                assert!(false, format!("{:?}",$res.unwrap_err()));
+            if let Err(e) = &$res {
+                panic!("{:?}", e);
             }
         }};
     }
@@ -244,11 +341,11 @@ mod tests {
 
         let root           = pkey.unwrap();
         let root_key_store = vec!(root);
-        let chain          = TrustChain::new(&root_key_store, Root::new(root));
+        let chain          = TrustChain::new(&root_key_store, Root::new(root), &SystemClock);
 
         assert_ok!(chain);
 
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root));
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root));
 
         assert_ok!(chain);
     }
@@ -267,12 +364,8 @@ mod tests {
         let root           = root_pkey.unwrap();
         let end            = end_pkey.unwrap();
         let root_key_store = vec!(root);
-        let sig            = sign_v3!(root_keypair, end.as_ref());
-
-        assert!(sig.is_some());
-
-        let link1          = Link::new(end, sig.unwrap());
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1);
+        let link1          = issue_link(&root_keypair, end, None).unwrap();
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1);
 
         assert_ok!(chain);
     }
@@ -296,93 +389,343 @@ mod tests {
         let intr           = intr_pkey.unwrap();
         let end            = end_pkey.unwrap();
         let root_key_store = vec!(root);
-        let root_sig       = sign_v3!(root_keypair, intr.as_ref());
-        let intr_sig       = sign_v3!(intr_keypair, end.as_ref());
-
-        assert!(root_sig.is_some());
-        assert!(intr_sig.is_some());
-
-        let link1          = Link::new(intr, root_sig.unwrap());
-        let link2          = Link::new(end,  intr_sig.unwrap());
-        let chain          = trust_chain_v3!(&root_key_store, Root::new(root), link1, link2);
+        let link1          = issue_link(&root_keypair, intr, None).unwrap();
+        let link2          = issue_link(&intr_keypair, end,  None).unwrap();
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1, link2);
 
         assert_ok!(chain);
 
-        let broken_chain   = trust_chain_v3!(&root_key_store, Root::new(root), link2, link1);
+        let broken_chain   = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link2, link1);
 
         assert_err!(broken_chain);
     }
 
-/*
+    fn timestamp (y:i32, m:u32, d:u32) -> u64 {
+        Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap().timestamp() as u64
+    }
+
     #[test]
-    fn four_link_chain_construction () {
+    fn expiring_link_chain_construction () {
         let rand           = SystemRandom::new();
         let root_keypair   = gen_keypair(&rand).unwrap();
-        let intr1_keypair  = gen_keypair(&rand).unwrap();
-        let intr2_keypair  = gen_keypair(&rand).unwrap();
         let end_keypair    = gen_keypair(&rand).unwrap();
-        let root_pkey      = PublicKey(root_keypair.public_key().as_ref());
-        let intr1_pkey     = PublicKey(intr1_keypair.public_key().as_ref());
-        let intr2_pkey     = PublicKey(intr2_keypair.public_key().as_ref());
-        let end_pkey       = PublicKey(end_keypair.public_key().as_ref());
-        let root_key_store = vec!(root_pkey);
-        let now            = Local::today();
-        let before         = date_from(now.pred()).unwrap();//date_from(Utc.ymd(2020, 1, 13)).unwrap();
-        let after          = date_from(now.succ()).unwrap();//date_from(Utc.ymd(2020, 1, 14)).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let before         = timestamp(2020, 1, 13);
+        let after          = timestamp(2020, 1, 14);
+        let expiry         = Expiry::new(NotBefore(before), NotAfter(after)).unwrap();
+        let link1          = issue_link(&root_keypair, end, Some(expiry)).unwrap();
+        let sig            = link1.sig();
+        let root_chain     = TrustChain::new(&root_key_store, Root::new(root), &FixedClock::new(before)).unwrap();
+
+        assert_ok!(root_chain.clone().append(link1, &FixedClock::new(before)));
+        assert_ok!(root_chain.clone().append(link1, &FixedClock::new(after)));
+        assert_eq!(root_chain.clone().append(link1, &FixedClock::new(before - 1)), Err(TrustError::InvalidExpiry));
+        assert_eq!(root_chain.clone().append(link1, &FixedClock::new(after + 1)),  Err(TrustError::InvalidExpiry));
+        assert_eq!(root_chain.clone().append(link1, &SystemClock), Err(TrustError::InvalidExpiry));
+
+        let as_of          = FixedClock::new(timestamp(2020, 1, 13) + 3600);
+        let chain          = root_chain.clone().append(link1, &as_of).unwrap();
+        let test_data      = b"test data";
+        let end_sig        = Signature::from(end_keypair.sign(test_data));
+
+        assert_ok!(chain.verify_data(&end_sig, test_data, &as_of));
+        assert_eq!(chain.verify_data(&end_sig, test_data, &SystemClock), Err(TrustError::InvalidExpiry));
+
+        // the window is covered by the signature, so it can not be widened after signing.
+        let widened        = Expiry::new(NotBefore(before), NotAfter(u64::MAX)).unwrap();
+        let forged         = Link::with_expiry(end, widened, sig);
+
+        assert_eq!(root_chain.clone().append(forged, &as_of), Err(TrustError::InvalidSignature));
+        assert_eq!(root_chain.clone().append(Link::new(end, sig), &as_of), Err(TrustError::InvalidSignature));
+    }
+
+    fn revocation_list_by (keypair: &signature::Ed25519KeyPair, revoked: Vec<PublicKey>) -> RevocationList {
+        let issuer         = PublicKey::from_slice(keypair.public_key().as_ref()).unwrap();
+        let signed_data    = RevocationList::signed_data(&issuer, &revoked).unwrap();
+        RevocationList::new(issuer, revoked, Signature::from(keypair.sign(&signed_data))).unwrap()
+    }
+
+    #[test]
+    fn revoked_links_should_fail_verification () {
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let alt_keypair    = gen_keypair(&rand).unwrap();
+        let intr_keypair   = gen_keypair(&rand).unwrap();
+        let end_keypair    = gen_keypair(&rand).unwrap();
+        let stray_keypair  = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let alt            = PublicKey::from_slice(alt_keypair.public_key().as_ref()).unwrap();
+        let intr           = PublicKey::from_slice(intr_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root, alt);
+        let link1          = issue_link(&root_keypair, intr, None).unwrap();
+        let link2          = issue_link(&intr_keypair, end,  None).unwrap();
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(root), link1, link2).unwrap();
+
+        assert_ok!(chain.verify_revocations(&root_key_store, &[]));
+
+        let by_intr        = revocation_list_by(&intr_keypair, vec!(end));
+        let by_root        = revocation_list_by(&root_keypair, vec!(intr));
+        let by_alt_root    = revocation_list_by(&alt_keypair,  vec!(root));
+        let by_stray       = revocation_list_by(&stray_keypair, vec!(intr, end));
+        let intr_on_root   = revocation_list_by(&intr_keypair, vec!(root));
+
+        assert_eq!(chain.verify_revocations(&root_key_store, &[by_intr]),     Err(TrustError::RevokedKey(end)));
+        assert_eq!(chain.verify_revocations(&root_key_store, &[by_root]),     Err(TrustError::RevokedKey(intr)));
+        assert_eq!(chain.verify_revocations(&root_key_store, &[by_alt_root]), Err(TrustError::RevokedKey(root)));
+        assert_ok!(chain.verify_revocations(&root_key_store, &[by_stray]));
+        assert_ok!(chain.verify_revocations(&root_key_store, &[intr_on_root]));
+    }
+
+    #[test]
+    fn four_link_chain_construction () {
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let before         = timestamp(2020, 1, 13);
+        let after          = timestamp(2020, 1, 14);
         let expiry         = Expiry::new(NotBefore(before),NotAfter(after)).unwrap();
-        let root_sig       = sign_v3!(root_keypair, expiry, intr1.as_ref());
-        let intr1_sig       = sign_v3!(intr1_keypair, expiry, intr2.as_ref());
-        let intr2_sig       = sign_v3!(intr2_keypair, expiry, end.as_ref());
-//        let root_sig       = root_keypair.sign(intr1_pkey.as_ref());
-//        let intr1_sig      = intr1_keypair.sign(intr2_pkey.as_ref());
-//        let intr2_sig      = intr2_keypair.sign(end_pkey.as_ref());
-
-        let chain = TrustChain::four_link_chain(
-            root_pkey,
-            intr1_pkey,
-            intr2_pkey,
-            end_pkey,
-            Signature::from_bytes(root_sig.as_ref()),
-            Signature::from_bytes(intr1_sig.as_ref()),
-            Signature::from_bytes(intr2_sig.as_ref()),
-            &root_key_store
-        );
+        let as_of          = FixedClock::new(before);
+        let chain          = ChainBuilder::new(&root_keypair)
+            .intermediate(Some(expiry))
+            .intermediate(Some(expiry))
+            .build(Some(expiry), &root_key_store, &as_of);
+
         assert_ok!(chain);
+
+        let (chain, end)   = chain.unwrap();
+        let end_sig        = Signature::from(end.sign(b"test data"));
+
+        assert_eq!(chain.len(), 4);
+        assert_ok!(chain.verify_data(&end_sig, b"test data", &as_of));
     }
 
     #[test]
     fn five_link_chain_construction () {
         let rand           = SystemRandom::new();
         let root_keypair   = gen_keypair(&rand).unwrap();
-        let intr1_keypair  = gen_keypair(&rand).unwrap();
-        let intr2_keypair  = gen_keypair(&rand).unwrap();
-        let intr3_keypair  = gen_keypair(&rand).unwrap();
-        let end_keypair    = gen_keypair(&rand).unwrap();
-        let root_pkey      = PublicKey(root_keypair.public_key().as_ref());
-        let intr1_pkey     = PublicKey(intr1_keypair.public_key().as_ref());
-        let intr2_pkey     = PublicKey(intr2_keypair.public_key().as_ref());
-        let intr3_pkey     = PublicKey(intr3_keypair.public_key().as_ref());
-        let end_pkey       = PublicKey(end_keypair.public_key().as_ref());
-        let root_key_store = vec!(root_pkey);
-        let root_sig       = root_keypair.sign(intr1_pkey.as_ref());
-        let intr1_sig      = intr1_keypair.sign(intr2_pkey.as_ref());
-        let intr2_sig      = intr2_keypair.sign(intr3_pkey.as_ref());
-        let intr3_sig      = intr3_keypair.sign(end_pkey.as_ref());
-
-        let chain = TrustChain::five_link_chain(
-            root_pkey,
-            intr1_pkey,
-            intr2_pkey,
-            intr3_pkey,
-            end_pkey,
-            Signature::from_bytes(root_sig.as_ref()),
-            Signature::from_bytes(intr1_sig.as_ref()),
-            Signature::from_bytes(intr2_sig.as_ref()),
-            Signature::from_bytes(intr3_sig.as_ref()),
-            &root_key_store
-        );
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let chain          = ChainBuilder::new(&root_keypair)
+            .intermediate(None)
+            .intermediate(None)
+            .intermediate(None)
+            .build(None, &root_key_store, &SystemClock);
+
         assert_ok!(chain);
+
+        let (chain, end)   = chain.unwrap();
+        let end_sig        = chain.sign_data(&end, b"test data").unwrap();
+
+        assert_eq!(chain.len(), 5);
+        assert_ok!(chain.verify_data(&end_sig, b"test data", &SystemClock));
+        assert_eq!(chain.sign_data(&root_keypair, b"test data"), Err(TrustError::SigningFailed));
+
+        // links issued without a key usage may all sign further links, so only the length stops a sixth.
+        let keypairs       = (0..MAXCHAINLINKS).map(|_| gen_keypair(&rand).unwrap()).collect::<Vec<_>>();
+        let keys           = keypairs.iter().map(|k| PublicKey::from_slice(k.public_key().as_ref()).unwrap()).collect::<Vec<_>>();
+        let link           = |at: usize| issue_link(&keypairs[at - 1], keys[at], None).unwrap();
+        let root_key_store = vec!(keys[0]);
+        let chain          = trust_chain_v3!(&root_key_store, &SystemClock, Root::new(keys[0]), link(1), link(2), link(3), link(4)).unwrap();
+        let end_pkey       = PublicKey::from_slice(end.public_key().as_ref()).unwrap();
+        let sixth_link     = issue_link(&keypairs[4], end_pkey, None).unwrap();
+
+        assert_eq!(chain.len(), 5);
+        assert_eq!(chain.append(sixth_link, &SystemClock), Err(TrustError::MaxChainLengthExceeded));
     }
-*/
 
+    #[test]
+    fn key_usage_should_be_enforced () {
+        use crate::v3::attributes::{Attributes, KeyUsage};
+        use crate::v3::builder::issue_link_with_attributes;
+
+        let rand           = SystemRandom::new();
+        let root_keypair   = gen_keypair(&rand).unwrap();
+        let intr_keypair   = gen_keypair(&rand).unwrap();
+        let end_keypair    = gen_keypair(&rand).unwrap();
+        let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+        let intr           = PublicKey::from_slice(intr_keypair.public_key().as_ref()).unwrap();
+        let end            = PublicKey::from_slice(end_keypair.public_key().as_ref()).unwrap();
+        let root_key_store = vec!(root);
+        let root_only      = TrustChain::new(&root_key_store, Root::new(root), &SystemClock).unwrap();
+        let usage          = |usage| Attributes::new().with_key_usage(usage).unwrap();
+        let issue          = |chain: &TrustChain, issuer, key, attributes| chain.clone().append(issue_link_with_attributes(issuer, key, None, attributes).unwrap(), &SystemClock);
+
+        // an intermediate may only issue links, its data signatures are refused however genuine.
+        let intr_chain     = issue(&root_only, &root_keypair, intr, usage(KeyUsage::SIGN_LINKS)).unwrap();
+        let intr_sig       = Signature::from(intr_keypair.sign(b"test data"));
+
+        assert_eq!(intr_chain.verify_data(&intr_sig, b"test data", &SystemClock), Err(TrustError::DataSigningNotPermitted));
+        assert_eq!(intr_chain.sign_data(&intr_keypair, b"test data"), Err(TrustError::DataSigningNotPermitted));
+
+        // an end key may only sign data, it cannot extend the chain any further.
+        let end_chain      = issue(&intr_chain, &intr_keypair, end, usage(KeyUsage::SIGN_DATA)).unwrap();
+        let end_sig        = end_chain.sign_data(&end_keypair, b"test data").unwrap();
+
+        assert_ok!(end_chain.verify_data(&end_sig, b"test data", &SystemClock));
+        assert_eq!(issue(&end_chain, &end_keypair, intr, Attributes::new()), Err(TrustError::LinkSigningNotPermitted));
+
+        // the builder issues its end key for data only, so it is refused before the length is even looked at.
+        let (built, built_end) = ChainBuilder::new(&root_keypair).build(None, &root_key_store, &SystemClock).unwrap();
+        let built_end_key  = PublicKey::from_slice(built_end.public_key().as_ref()).unwrap();
+
+        assert_eq!(built.append(issue_link(&built_end, built_end_key, None).unwrap(), &SystemClock), Err(TrustError::LinkSigningNotPermitted));
+
+        // keys issued for both, or before roles existed, may do either.
+        for attributes in [usage(KeyUsage::BOTH), Attributes::new()] {
+            let chain      = issue(&root_only, &root_keypair, intr, attributes).unwrap();
+
+            assert_ok!(chain.verify_data(&intr_sig, b"test data", &SystemClock));
+            assert_ok!(issue(&chain, &intr_keypair, end, Attributes::new()));
+        }
+    }
+
+    #[test]
+    fn max_depth_should_hold_beneath_the_link_that_set_it () {
+        use crate::v3::attributes::Attributes;
+        use crate::v3::builder::issue_link_with_attributes;
+
+        let rand           = SystemRandom::new();
+        let keypairs       = (0..5).map(|_| gen_keypair(&rand).unwrap()).collect::<Vec<_>>();
+        let keys           = keypairs.iter().map(|k| PublicKey::from_slice(k.public_key().as_ref()).unwrap()).collect::<Vec<_>>();
+        let root_key_store = vec!(keys[0]);
+        let depth          = |max| Attributes::new().with_max_depth(max).unwrap();
+        let extend         = |chain: TrustChain, attributes| {
+            let at         = chain.len();
+            chain.append(issue_link_with_attributes(&keypairs[at - 1], keys[at], None, attributes).unwrap(), &SystemClock)
+        };
+        let root_only      = TrustChain::new(&root_key_store, Root::new(keys[0]), &SystemClock).unwrap();
+
+        // a team intermediate held to issuing end keys only.
+        let team           = extend(root_only.clone(), depth(1)).unwrap();
+
+        assert_eq!(team.depth_limit(), Some(2));
+
+        let end            = extend(team, Attributes::new()).unwrap();
+
+        assert_eq!(extend(end, Attributes::new()), Err(TrustError::PathLengthExceeded));
+
+        // a looser limit further down never undoes a tighter one above it.
+        let tight          = extend(extend(root_only.clone(), depth(2)).unwrap(), depth(4)).unwrap();
+
+        assert_eq!(tight.depth_limit(), Some(3));
+        assert_eq!(extend(extend(tight, Attributes::new()).unwrap(), Attributes::new()), Err(TrustError::PathLengthExceeded));
+
+        // while a tighter one further down does take over, and 0 leaves nothing to issue.
+        let tighter        = extend(extend(root_only.clone(), depth(3)).unwrap(), depth(0)).unwrap();
+
+        assert_eq!(tighter.depth_limit(), Some(2));
+        assert_eq!(extend(tighter, Attributes::new()), Err(TrustError::PathLengthExceeded));
+        assert_eq!(root_only.depth_limit(), None);
+    }
+
+    #[test]
+    fn chains_should_hold_up_to_their_capacity () {
+        use crate::v3::parsers::{trust_chain, trust_chain_bounded, trust_chain_ref, trust_chain_ref_bounded};
+
+        let rand           = SystemRandom::new();
+        let keypairs       = (0..8).map(|_| gen_keypair(&rand).unwrap()).collect::<Vec<_>>();
+        let keys           = keypairs.iter().map(|k| PublicKey::from_slice(k.public_key().as_ref()).unwrap()).collect::<Vec<_>>();
+        let root_key_store = vec!(keys[0]);
+        let link           = |at: usize| issue_link(&keypairs[at - 1], keys[at], None).unwrap();
+
+        // a verifier that only ever expects a root and an end key.
+        let mut short      = TrustChain::<2>::bounded(&root_key_store, Root::new(keys[0]), &SystemClock).unwrap();
+        short              = short.append(link(1), &SystemClock).unwrap();
+
+        assert_eq!(short.capacity(), 2);
+        assert_eq!(short.last().key(), keys[1]);
+        assert_eq!(short.clone().append(link(2), &SystemClock).map(|_| ()), Err(TrustError::MaxChainLengthExceeded));
+        assert_eq!(trust_chain(&mut short.as_bytes().as_slice(), &root_key_store, &SystemClock).map(|c| c.as_bytes()), Ok(short.as_bytes()));
+
+        // and one deeper than MAXCHAINLINKS, which only verifiers expecting that depth read back.
+        let mut deep       = TrustChain::<8>::bounded(&root_key_store, Root::new(keys[0]), &SystemClock).unwrap();
+        for at in 1..8 {
+            deep           = deep.append(link(at), &SystemClock).unwrap();
+        }
+        let mut written    = Vec::new();
+        deep.write_to(&mut written).unwrap();
+
+        assert_eq!(deep.len(), 8);
+        assert_eq!(deep.iter().map(|l| l.key()).collect::<Vec<_>>(), keys);
+        assert_eq!(deep.first().key(), keys[0]);
+        assert_eq!(deep.last().key(), keys[7]);
+        assert_eq!(written, deep.as_bytes());
+        assert_eq!(written.len(), deep.encoded_len());
+        assert_eq!(written[4], 8);
+        assert_eq!(trust_chain(&mut written.as_slice(), &root_key_store, &SystemClock), Err(TrustError::MaxChainLengthExceeded));
+        assert_eq!(trust_chain_bounded::<_, 7>(&mut written.as_slice(), &root_key_store, &SystemClock).map(|_| ()), Err(TrustError::MaxChainLengthExceeded));
+        assert_eq!(trust_chain_bounded::<_, 8>(&mut written.as_slice(), &root_key_store, &SystemClock), Ok(deep.clone()));
+        assert_eq!(trust_chain_ref_bounded::<8>(&written, &root_key_store, &SystemClock).map(|c| c.last().key()), Ok(keys[7]));
+        assert_eq!(trust_chain_ref(&written, &root_key_store, &SystemClock).map(|_| ()), Err(TrustError::MaxChainLengthExceeded));
+    }
+
+    mod round_trip {
+        use crate::v3::parsers::{trust_chain, trust_chain_ref_strict};
+        use proptest::prelude::*;
+        use super::*;
+
+        const NOW: u64 = 1_600_000_000;
+
+        // a key seed plus an optional window straddling NOW by the given margins.
+        type LinkSpec = ([u8;32], Option<(u64, u64)>);
+
+        fn links () -> impl Strategy<Value = Vec<LinkSpec>> {
+            proptest::collection::vec((any::<[u8;32]>(), proptest::option::of((0..NOW, 0..NOW))), 0..MAXCHAINLINKS as usize)
+        }
+
+        fn build (root_seed: &[u8;32], links: &[LinkSpec]) -> (Vec<PublicKey>, TrustChain) {
+            let root_keypair   = signature::Ed25519KeyPair::from_seed_unchecked(root_seed).unwrap();
+            let root           = PublicKey::from_slice(root_keypair.public_key().as_ref()).unwrap();
+            let root_key_store = vec!(root);
+            let mut chain      = TrustChain::new(&root_key_store, Root::new(root), &SystemClock).unwrap();
+            let mut issuer     = root_keypair;
+
+            for (seed, window) in links {
+                let keypair    = signature::Ed25519KeyPair::from_seed_unchecked(seed).unwrap();
+                let key        = PublicKey::from_slice(keypair.public_key().as_ref()).unwrap();
+                let expiry     = window.map(|(before, after)| Expiry::new(NotBefore(NOW - before), NotAfter(NOW + after)).unwrap());
+                chain          = chain.append(issue_link(&issuer, key, expiry).unwrap(), &FixedClock::new(NOW)).unwrap();
+                issuer         = keypair;
+            }
+            (root_key_store, chain)
+        }
+
+        proptest! {
+            #[test]
+            fn write_to_and_trust_chain_should_invert_each_other (root_seed in any::<[u8;32]>(), links in links()) {
+                let (root_key_store, chain) = build(&root_seed, &links);
+                let clock      = FixedClock::new(NOW);
+                let mut out    = Vec::new();
+                chain.write_to(&mut out).unwrap();
+
+                prop_assert_eq!(out.len(), chain.encoded_len());
+                prop_assert_eq!(&out, &chain.as_bytes());
+                prop_assert_eq!(trust_chain(&mut out.as_slice(), &root_key_store, &clock), Ok(chain.clone()));
+                prop_assert_eq!(trust_chain_ref_strict(&out, &root_key_store, &clock).map(|c| c.len()), Ok(chain.len()));
+            }
+
+            #[test]
+            fn chains_written_back_to_back_should_read_back_in_order (a in links(), b in links(), prefix in any::<Vec<u8>>()) {
+                let root_seed  = [0x42;32];
+                let (root_key_store, first) = build(&root_seed, &a);
+                let (_, second) = build(&root_seed, &b);
+                let clock      = FixedClock::new(NOW);
+                let mut out    = prefix.clone();
+                first.write_to(&mut out).unwrap();
+                second.write_to(&mut out).unwrap();
+
+                prop_assert_eq!(out.len(), prefix.len() + first.encoded_len() + second.encoded_len());
+
+                let mut input  = &out[prefix.len()..];
+
+                prop_assert_eq!(trust_chain(&mut input, &root_key_store, &clock), Ok(first));
+                prop_assert_eq!(trust_chain(&mut input, &root_key_store, &clock), Ok(second));
+                prop_assert!(input.is_empty());
+            }
+        }
+    }
 }
@@ -0,0 +1,128 @@
+use ring::signature;
+use crate::v3::MAXCHAINLINKS;
+use crate::v3::error::TrustError;
+use crate::v3::key::PublicKey;
+use crate::v3::signature::Signature;
+use crate::v3::expiry::Expiry;
+use crate::v3::link::{TrustLink, MAX_SIGNED_DATA_LEN, write_signed_data};
+use crate::v3::attributes::Attributes;
+use crate::v3::anchor::TrustAnchor;
+use crate::v3::clock::Clock;
+use core::fmt;
+
+//--- A link whose key and signature still live in the encoded chain
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct LinkRef<'a> {
+    pub(crate) key: &'a [u8],
+    pub(crate) expiry: Option<Expiry>,
+    // the checked attribute block, empty when the link carries none.
+    pub(crate) attributes: &'a [u8],
+    pub(crate) sig: Option<&'a [u8]>,
+}
+
+impl <'a> LinkRef<'a> {
+    pub fn key_bytes (&self) -> &'a [u8] { self.key }
+
+    pub fn signature_bytes (&self) -> Option<&'a [u8]> { self.sig }
+}
+
+impl <'a> TrustLink for LinkRef<'a> {
+    // safe because the parser only hands out links whose slices have the exact key and signature lengths.
+    fn key       (&self) -> PublicKey {PublicKey::from_slice(self.key).unwrap()}
+    fn signature (&self) -> Option<Signature> {self.sig.map(|sig| Signature::from_slice(sig).unwrap())}
+    fn expiry    (&self) -> Option<Expiry> {self.expiry}
+    fn is_root   (&self) -> bool {self.sig.is_none()}
+    fn attributes (&self) -> Attributes {Attributes::from_slice(self.attributes).unwrap()}
+}
+
+//--- Same bytes as Link::signed_data, assembled on the stack so verification never allocates
+pub(crate) fn verify_link (issuer: &[u8], link: &LinkRef) -> Result<(),TrustError> {
+    let mut signed = [0u8;MAX_SIGNED_DATA_LEN];
+    let len = write_signed_data(&mut signed, link.key, link.expiry.as_ref(), link.attributes);
+    let sig = link.sig.ok_or(TrustError::InvalidTrustChain)?;
+    signature::UnparsedPublicKey::new(&signature::ED25519, issuer)
+        .verify(&signed[..len], sig)
+        .map_err(|_| TrustError::InvalidSignature)
+}
+
+//--- A verified chain borrowed from its encoding, built by parsers::trust_chain_ref without copying or allocating
+#[derive(Clone,PartialEq)]
+pub struct TrustChainRef<'a, const N: usize = { MAXCHAINLINKS as usize }> {
+    pub(crate) anchor: TrustAnchor,
+    pub(crate) bytes: &'a [u8],
+    pub(crate) len: u8,
+    pub(crate) links: [LinkRef<'a>; N],
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl <'a, const N: usize> TrustChainRef<'a, N> {
+
+    pub fn iter (&self) -> impl Iterator<Item = &dyn TrustLink> + '_ {
+        self.links[..self.len()].iter().map(|l| l as &dyn TrustLink)
+    }
+
+    pub fn links (&self) -> &[LinkRef<'a>] {
+        &self.links[..self.len()]
+    }
+
+    pub fn len (&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn first (&self) -> &dyn TrustLink {
+        &self.links[0]
+    }
+
+    //--- the anchor that validated this chain
+    pub fn anchor (&self) -> &TrustAnchor {
+        &self.anchor
+    }
+
+    pub fn last (&self) -> &dyn TrustLink {
+        &self.links[self.len() - 1]
+    }
+
+    pub fn is_valid_at (&self, at:u64) -> Result<(),TrustError> {
+        self.anchor.is_valid_at(at)?;
+        for l in self.links() {
+            if let Some(expiry) = l.expiry {
+                if !expiry.is_valid_at(at) { return Err(TrustError::InvalidExpiry) }
+            }
+        }
+        Ok(())
+    }
+
+    //--- Opt-in check that every critical attribute in the chain is standard or named in `understood`
+    pub fn verify_critical_attributes (&self, understood: &[u8]) -> Result<(),TrustError> {
+        self.iter().try_for_each(|l| l.attributes().check_critical(understood))
+    }
+
+    pub fn verify_data (&self, untrusted_signature: &Signature, untrusted_data: &[u8], clock: &dyn Clock) -> Result<(),TrustError> {
+        self.is_valid_at(clock.now())?;
+        if !self.last().attributes().permits_data_signing() { return Err(TrustError::DataSigningNotPermitted) }
+        signature::UnparsedPublicKey::new(&signature::ED25519, self.links[self.len() - 1].key)
+            .verify(untrusted_data, untrusted_signature.as_ref())
+            .map_err(|_| TrustError::InvalidSignature)
+    }
+
+    //--- exactly the encoded chain, without whatever followed it in the parsed input
+    pub fn as_bytes (&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl <'a, const N: usize> fmt::Debug for TrustChainRef<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.links().iter()).finish()
+    }
+}
+
+impl <'a, const N: usize> fmt::Display for TrustChainRef<'a, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex_str = self.bytes
+            .iter()
+            .map(|b| format!("{:02x?}",b))
+            .collect::<String>();
+        write!(f, "{}", hex_str)
+    }
+}
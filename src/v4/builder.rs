@@ -0,0 +1,13 @@
+use crate::v4::{TrustError, PublicKey, Expiry, Signer};
+use crate::v4::link::Link;
+
+//--- Signs a child key, and its validity window when given, as the link at `depth` beneath the issuer
+//
+// depth counts from the root, so the first link below a root is at depth 1.
+pub fn issue_link (issuer: &dyn Signer, depth: u8, key: PublicKey, expiry: Option<Expiry>) -> Result<Link, TrustError> {
+    let sig = issuer.sign(&Link::signed_data(&issuer.key(), depth, &key, expiry.as_ref()))?;
+    Ok(match expiry {
+        Some(expiry) => Link::with_expiry(key, expiry, sig),
+        None         => Link::new(key, sig),
+    })
+}
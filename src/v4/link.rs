@@ -0,0 +1,59 @@
+use crate::v4::{PublicKey, Signature, Expiry, Key, Signed, TrustLink, LINK_FLAG_EXPIRY};
+use crate::v4::{TC_V4_HEADER, LINK_SIGNATURE_CONTEXT, PUBLICKEYBYTES, EXPIRYBYTES};
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Link {
+    key: PublicKey,
+    expiry: Option<Expiry>,
+    sig: Signature,
+}
+
+impl Link {
+    pub fn new (key: PublicKey, sig: Signature) -> Link {
+        Link {key, expiry: None, sig}
+    }
+
+    pub fn with_expiry (key: PublicKey, expiry: Expiry, sig: Signature) -> Link {
+        Link {key, expiry: Some(expiry), sig}
+    }
+
+    pub fn flags (&self) -> u8 {
+        if self.expiry.is_some() { LINK_FLAG_EXPIRY } else { 0 }
+    }
+
+    //--- The bytes covered by the parent's signature
+    //
+    // context, chain version, the link's depth below the root, the parent key, the flags, the validity
+    // window (when present) and last the key, so a signature is only good for one place in one chain.
+    pub fn signed_data (parent: &PublicKey, depth: u8, key: &PublicKey, expiry: Option<&Expiry>) -> Vec<u8> {
+        let mut data = Vec::with_capacity(LINK_SIGNATURE_CONTEXT.len() + 2 + 1 + PUBLICKEYBYTES + 1 + EXPIRYBYTES + PUBLICKEYBYTES);
+        data.extend_from_slice(LINK_SIGNATURE_CONTEXT);
+        data.extend_from_slice(&TC_V4_HEADER[2..]);
+        data.push(depth);
+        data.extend_from_slice(parent.as_ref());
+        match expiry {
+            Some(expiry) => {
+                data.push(LINK_FLAG_EXPIRY);
+                data.extend_from_slice(&expiry.as_bytes());
+            },
+            None => data.push(0x00),
+        }
+        data.extend_from_slice(key.as_ref());
+        data
+    }
+}
+
+impl Key for Link {
+    fn key       (&self) -> PublicKey {self.key}
+}
+
+impl Signed for Link {
+    fn sig (&self) -> Signature {self.sig}
+}
+
+impl TrustLink for Link {
+    fn key       (&self) -> PublicKey {self.key}
+    fn signature (&self) -> Option<Signature> {Some(self.sig)}
+    fn expiry    (&self) -> Option<Expiry> {self.expiry}
+    fn is_root   (&self) -> bool {false}
+}
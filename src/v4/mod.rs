@@ -0,0 +1,26 @@
+// v4 keeps v3's algorithms and field encodings, only what link and data signatures cover changes,
+// so the primitives are shared with v3 rather than defined a second time.
+pub use crate::v3::{
+    PUBLICKEYBYTES, SIGNATUREBYTES, EXPIRYBYTES, MAXCHAINLINKS, LINK_FLAG_EXPIRY,
+    TrustError, PublicKey, Signature, Expiry, NotBefore, NotAfter, Clock, SystemClock, FixedClock,
+    Root, Key, Signed, TrustLink, TrustAnchor, RootKeysStore, Signer, RevocationList,
+};
+
+// ------------------------------[   T,   C,  v.    4]
+pub const TC_V4_HEADER: [u8;4] = [0x54,0x43,0x00,0x04];
+
+// Leads the bytes of every link signature, so no signature made for any other purpose verifies as a link.
+pub const LINK_SIGNATURE_CONTEXT: &[u8] = b"trustchain link signature\x00";
+
+// Leads the bytes of every data signature, so an end key's data signature never verifies as a link, nor a link as data.
+pub const DATA_SIGNATURE_CONTEXT: &[u8] = b"trustchain data signature\x00";
+
+mod link;
+mod trust_chain;
+mod parsers;
+mod builder;
+
+pub use link::*;
+pub use trust_chain::*;
+pub use parsers::*;
+pub use builder::*;
@@ -0,0 +1,95 @@
+use crate::v4::{TC_V4_HEADER, PUBLICKEYBYTES, MAXCHAINLINKS, LINK_FLAG_EXPIRY};
+use crate::v4::{TrustError, PublicKey, Root, RootKeysStore, Clock};
+use crate::v3::decoder::{LinkLayout, read_field, read_link_with};
+use crate::v4::link::Link;
+use crate::v4::trust_chain::TrustChain;
+use std::io::{self, Read};
+
+// v4 links are laid out as flagged v3 links that may only carry an expiry, so the v3 decoder reads them.
+fn read_link <R> (input: &mut R) -> Result<Link, TrustError>
+where R: Read  {
+    read_link_with(input, LinkLayout::Flagged(LINK_FLAG_EXPIRY), |link| match link.expiry {
+        Some(expiry) => Link::with_expiry(link.public_key(), expiry, link.signature()),
+        None         => Link::new(link.public_key(), link.signature()),
+    })
+}
+
+pub fn trust_chain <'ks, R> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain,TrustError>
+where R: Read  {
+    let mut buf = [0u8;5 + PUBLICKEYBYTES];
+    read_field(input, &mut buf[..5])?;
+
+    if buf[0..4] != TC_V4_HEADER {
+        return Err(TrustError::InvalidTrustChain)
+    }
+
+    let chain_length = buf[4];
+    if chain_length > MAXCHAINLINKS {
+        return Err(TrustError::MaxChainLengthExceeded)
+    }
+
+    if chain_length < 1 {
+        return Err(TrustError::InvalidTrustChain)
+    }
+
+    read_field(input, &mut buf[5..])?;
+    let root = PublicKey::from_slice(&buf[5..]).ok_or(TrustError::InvalidTrustChain)?;
+
+    let mut chain = TrustChain::new(root_keys_store, Root::new(root), clock)?;
+    for _ in 1..chain_length {
+        chain = chain.append(read_link(input)?, clock)?;
+    }
+    Ok(chain)
+}
+
+//--- As trust_chain, but the input must end with the last link, so only use it on inputs that do end
+pub fn trust_chain_strict <'ks, R> (input: &mut R, root_keys_store:&'ks (dyn RootKeysStore<'ks> + 'ks), clock: &dyn Clock) -> Result<TrustChain,TrustError>
+where R: Read  {
+    let chain = trust_chain(input, root_keys_store, clock)?;
+    let mut buf = [0u8;1];
+    loop {
+        match input.read(&mut buf) {
+            Ok(0)  => return Ok(chain),
+            Ok(_)  => return Err(TrustError::TrailingData),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(TrustError::ReadFailed(e.kind())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v4::{Expiry, FixedClock, NotBefore, NotAfter, Signer};
+    use crate::v4::builder::issue_link;
+    use ring::signature::Ed25519KeyPair;
+    use super::*;
+
+    fn keypair (seed: u8) -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[seed;32]).unwrap()
+    }
+
+    #[test]
+    fn chain_should_round_trip_through_the_wire_format () {
+        let (root, mid, end) = (keypair(0x04), keypair(0x05), keypair(0x06));
+        let root_key_store = vec!(root.key());
+        let clock          = FixedClock::new(1_500);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let chain          = TrustChain::new(&root_key_store, Root::new(root.key()), &clock)
+            .and_then(|c| c.append(issue_link(&root, 1, mid.key(), Some(expiry))?, &clock))
+            .and_then(|c| c.append(issue_link(&mid, 2, end.key(), None)?, &clock))
+            .unwrap();
+        let cb             = chain.as_bytes();
+
+        assert_eq!(trust_chain_strict(&mut cb.as_slice(), &root_key_store, &clock).as_ref(), Ok(&chain));
+        assert_eq!(trust_chain(&mut cb.as_slice(), &root_key_store, &FixedClock::new(2_001)), Err(TrustError::InvalidExpiry));
+        assert_eq!(trust_chain(&mut cb.as_slice(), &Vec::<PublicKey>::new(), &clock), Err(TrustError::NoRootKeyTrust));
+        assert_eq!(trust_chain(&mut &cb[..cb.len() - 1], &root_key_store, &clock), Err(TrustError::UnexpectedEof));
+        assert_eq!(trust_chain_strict(&mut [&cb[..], &[0x00]].concat().as_slice(), &root_key_store, &clock), Err(TrustError::TrailingData));
+
+        let mut as_v3      = cb.clone();
+        as_v3[3]           = 0x03;
+
+        assert_eq!(trust_chain(&mut as_v3.as_slice(), &root_key_store, &clock), Err(TrustError::InvalidTrustChain));
+        assert_eq!(crate::v3::trust_chain(&mut as_v3.as_slice(), &root_key_store, &clock), Err(TrustError::InvalidSignature));
+    }
+}
@@ -0,0 +1,247 @@
+use ring::signature;
+use crate::v4::{TC_V4_HEADER, DATA_SIGNATURE_CONTEXT, PUBLICKEYBYTES, SIGNATUREBYTES, EXPIRYBYTES, MAXCHAINLINKS, LINK_FLAG_EXPIRY};
+use crate::v4::{TrustError, Signature, Root, Signed, TrustLink, TrustAnchor, RootKeysStore, Clock, Signer, RevocationList};
+use crate::v4::link::Link;
+use core::fmt;
+use std::convert::TryInto;
+use std::io::{self, Write};
+
+fn verify_signature (key: &[u8], sig: &[u8], data: &[u8]) -> Result<(),TrustError> {
+    signature::UnparsedPublicKey::new(&signature::ED25519, key)
+        .verify(data, sig)
+        .map_err(|_| TrustError::InvalidSignature)
+}
+
+//--- A verified v4 chain: the anchor that vouched for the root, then up to MAXCHAINLINKS - 1 links
+#[derive(Clone,Debug,PartialEq)]
+pub struct TrustChain {
+    anchor: TrustAnchor,
+    links: Vec<Link>,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl TrustChain {
+
+    //--- Starts a chain at a root the store holds an anchor for, provided the anchor admits it at this time
+    pub fn new <'ks> (
+        root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+        root: Root,
+        clock: &dyn Clock ) -> Result<TrustChain, TrustError> {
+
+        let anchor = root_key_store.trust_anchor(root.key().as_ref()).ok_or(TrustError::NoRootKeyTrust)?;
+        anchor.admits_at(clock.now())?;
+
+        Ok(TrustChain { anchor, links: Vec::with_capacity(MAXCHAINLINKS as usize - 1) })
+    }
+
+    //--- the anchor that validated this chain
+    pub fn anchor (&self) -> &TrustAnchor { &self.anchor }
+
+    pub fn iter (&self) -> impl Iterator<Item = &dyn TrustLink> + '_ {
+        Some(&self.anchor as &dyn TrustLink).into_iter().chain(self.links.iter().map(|l| l as &dyn TrustLink))
+    }
+
+    pub fn len (&self) -> usize { 1 + self.links.len() }
+
+    pub fn first (&self) -> &dyn TrustLink { &self.anchor }
+
+    pub fn last (&self) -> &dyn TrustLink {
+        self.links.last().map(|l| l as &dyn TrustLink).unwrap_or(&self.anchor)
+    }
+
+    //--- the link must have been signed by the current end key for exactly the depth it is appended at
+    pub fn append (mut self, l:Link, clock: &dyn Clock) -> Result<TrustChain, TrustError> {
+        // safe because we prevent creation beyond max chain length.
+        let depth       = self.len().try_into().unwrap();
+        let signed_data = Link::signed_data(&self.last().key(), depth, &l.key(), l.expiry().as_ref());
+        verify_signature(self.last().key().as_ref(), l.sig().as_ref(), &signed_data)?;
+
+        if let Some(expiry) = l.expiry() {
+            if !expiry.is_valid_at(clock.now()) { return Err(TrustError::InvalidExpiry) }
+        }
+
+        self.anchor.allows_depth(self.len())?;
+
+        if self.len() >= MAXCHAINLINKS as usize { return Err(TrustError::MaxChainLengthExceeded) }
+        self.links.push(l);
+        Ok(self)
+    }
+
+    pub fn is_valid_at (&self, at:u64) -> Result<(),TrustError> {
+        self.anchor.is_valid_at(at)?;
+        for l in self.links.iter() {
+            if let Some(expiry) = l.expiry() {
+                if !expiry.is_valid_at(at) { return Err(TrustError::InvalidExpiry) }
+            }
+        }
+        Ok(())
+    }
+
+    // revocation lists are v3's, and cover v4 chains on the same terms.
+    pub fn verify_revocations <'ks> (
+        &self,
+        root_key_store: &'ks (dyn RootKeysStore<'ks> + 'ks),
+        revocation_lists: &[RevocationList] ) -> Result<(),TrustError> {
+
+        for list in revocation_lists.iter() {
+            let issuer = list.issuer();
+            let mut authoritative = root_key_store.contains_root_key(issuer.as_ref());
+            for l in self.iter() {
+                authoritative |= l.key() == issuer;
+                if authoritative && list.is_revoked(&l.key()) {
+                    return Err(TrustError::RevokedKey(l.key()))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //--- Data signatures cover DATA_SIGNATURE_CONTEXT ahead of the data, so only sign_data's verify here
+    pub fn verify_data (&self, untrusted_signature: &Signature, untrusted_data: &[u8], clock: &dyn Clock) -> Result<(),TrustError> {
+        self.is_valid_at(clock.now())?;
+        verify_signature(self.last().key().as_ref(), untrusted_signature.as_ref(), &[DATA_SIGNATURE_CONTEXT, untrusted_data].concat())
+    }
+
+    //--- Signs data as the holder of this chain's end key, refusing any other signer
+    pub fn sign_data (&self, signer: &dyn Signer, data: &[u8]) -> Result<Signature,TrustError> {
+        if signer.key() != self.last().key() { return Err(TrustError::SigningFailed) }
+        signer.sign(&[DATA_SIGNATURE_CONTEXT, data].concat())
+    }
+
+    pub fn encoded_len (&self) -> usize {
+        let links = self.links.iter()
+            .map(|l| PUBLICKEYBYTES + 1 + l.expiry().map_or(0, |_| EXPIRYBYTES) + SIGNATUREBYTES)
+            .sum::<usize>();
+        TC_V4_HEADER.len() + 1 + PUBLICKEYBYTES + links
+    }
+
+    pub fn write_to <W: Write> (&self, out: &mut W) -> io::Result<()> {
+        out.write_all(&self.as_bytes())
+    }
+
+    pub fn as_bytes (&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        buf.extend_from_slice(&TC_V4_HEADER);
+        buf.push(self.len().try_into().unwrap());// safe because we prevent creation beyond max chain length.
+        buf.extend_from_slice(self.anchor.key().as_ref());
+        for l in self.links.iter() {
+            buf.extend_from_slice(l.key().as_ref());
+            match l.expiry() {
+                Some(expiry) => {
+                    buf.push(LINK_FLAG_EXPIRY);
+                    buf.extend_from_slice(&expiry.as_bytes());
+                },
+                None => buf.push(0x00),
+            }
+            buf.extend_from_slice(l.sig().as_ref());
+        }
+        buf
+    }
+}
+
+impl fmt::Display for TrustChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex_str = self.as_bytes()
+            .iter()
+            .map(|b| format!("{:02x?}",b))
+            .collect::<String>();
+        write!(f, "{}", hex_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v4::{PublicKey, Expiry, NotBefore, NotAfter, FixedClock};
+    use crate::v4::builder::issue_link;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use super::*;
+
+    fn keypair (seed: u8) -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[seed;32]).unwrap()
+    }
+
+    #[test]
+    fn chain_should_verify_links_signed_for_their_place () {
+        let (root, mid, end) = (keypair(0x01), keypair(0x02), keypair(0x03));
+        let root_key_store = vec!(root.key());
+        let clock          = FixedClock::new(1_500);
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let chain          = TrustChain::new(&root_key_store, Root::new(root.key()), &clock)
+            .and_then(|c| c.append(issue_link(&root, 1, mid.key(), Some(expiry))?, &clock))
+            .and_then(|c| c.append(issue_link(&mid, 2, end.key(), None)?, &clock))
+            .unwrap();
+        let sig            = chain.sign_data(&end, b"test data").unwrap();
+
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.first().key(), root.key());
+        assert_eq!(chain.last().key(), end.key());
+        assert_eq!(chain.iter().map(|l| l.key()).collect::<Vec<_>>(), vec!(root.key(), mid.key(), end.key()));
+        assert_eq!(chain.encoded_len(), chain.as_bytes().len());
+        assert_eq!(&chain.as_bytes()[..4], b"TC\x00\x04");
+        assert_eq!(chain.verify_data(&sig, b"test data", &clock), Ok(()));
+        assert_eq!(chain.verify_data(&sig, b"test data", &FixedClock::new(2_001)), Err(TrustError::InvalidExpiry));
+
+        // a bare signature over the data is no data signature, and signing a link's bytes as data issues no link.
+        let link_data      = Link::signed_data(&end.key(), 3, &root.key(), None);
+        let as_data        = chain.sign_data(&end, &link_data).unwrap();
+
+        assert_eq!(chain.verify_data(&Signature::from(end.sign(b"test data")), b"test data", &clock), Err(TrustError::InvalidSignature));
+        assert_eq!(chain.clone().append(Link::new(root.key(), as_data), &clock), Err(TrustError::InvalidSignature));
+
+        let revoked        = vec!(mid.key());
+        let list           = RevocationList::signed_data(&root.key(), &revoked)
+            .and_then(|data| RevocationList::new(root.key(), revoked, Signature::from(root.sign(&data))))
+            .unwrap();
+
+        assert_eq!(chain.verify_revocations(&root_key_store, &[list]), Err(TrustError::RevokedKey(mid.key())));
+    }
+
+    #[test]
+    fn signatures_made_for_anything_else_should_not_verify_as_links () {
+        let (root, mid, end) = (keypair(0x01), keypair(0x02), keypair(0x03));
+        let root_key_store = vec!(root.key());
+        let clock          = FixedClock::new(1_500);
+        let root_chain     = TrustChain::new(&root_key_store, Root::new(root.key()), &clock).unwrap();
+        let mid_chain      = root_chain.clone().append(issue_link(&root, 1, mid.key(), None).unwrap(), &clock).unwrap();
+        let raw            = Signature::from(root.sign(end.key().as_ref()));
+        let v3_link        = Signature::from(root.sign(&crate::v3::Link::signed_data(&end.key(), None)));
+
+        let cases = vec!(
+            // a bare signature over the key, as a v3 link carries.
+            (root_chain.clone(), Link::new(end.key(), raw)),
+            (root_chain.clone(), Link::new(end.key(), v3_link)),
+            // signed for another depth.
+            (root_chain.clone(), issue_link(&root, 2, end.key(), None).unwrap()),
+            (mid_chain.clone(), issue_link(&mid, 1, end.key(), None).unwrap()),
+            // signed by the right key for a different parent.
+            (mid_chain.clone(), Link::new(end.key(), Signature::from(mid.sign(&Link::signed_data(&root.key(), 2, &end.key(), None))))),
+        );
+
+        for (chain, link) in cases {
+            assert_eq!(chain.append(link, &clock), Err(TrustError::InvalidSignature));
+        }
+
+        let expiry         = Expiry::new(NotBefore(1_000), NotAfter(2_000)).unwrap();
+        let windowed       = issue_link(&root, 1, end.key(), Some(expiry)).unwrap();
+
+        assert_eq!(root_chain.append(Link::new(end.key(), windowed.sig()), &clock), Err(TrustError::InvalidSignature));
+    }
+
+    #[test]
+    fn chain_should_stop_at_max_chain_links () {
+        let keys           = (1..=MAXCHAINLINKS + 1).map(keypair).collect::<Vec<_>>();
+        let root_key_store = vec!(PublicKey::from_slice(keys[0].public_key().as_ref()).unwrap());
+        let clock          = FixedClock::new(1_500);
+        let mut chain      = TrustChain::new(&root_key_store, Root::new(keys[0].key()), &clock).unwrap();
+
+        for depth in 1..MAXCHAINLINKS {
+            let (parent, child) = (&keys[depth as usize - 1], &keys[depth as usize]);
+            chain = chain.append(issue_link(parent, depth, child.key(), None).unwrap(), &clock).unwrap();
+        }
+
+        let extra          = issue_link(&keys[MAXCHAINLINKS as usize - 1], MAXCHAINLINKS, keys[MAXCHAINLINKS as usize].key(), None).unwrap();
+
+        assert_eq!(chain.len(), MAXCHAINLINKS as usize);
+        assert_eq!(chain.append(extra, &clock), Err(TrustError::MaxChainLengthExceeded));
+    }
+}